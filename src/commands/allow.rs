@@ -0,0 +1,44 @@
+use dialoguer::Confirm;
+use std::fs;
+use std::path::Path;
+
+use crate::error::Result;
+use crate::keychain::TrustStore;
+use crate::project::{self, Manifest};
+
+/// Show a project manifest and, once approved, pin its current content
+///
+/// `hook-shell` only loads manifests pinned here, and only from the service
+/// shown: the manifest's own, or `--service-name` when it names none.
+/// Editing the file (or checking out a different version) revokes the
+/// approval until it is given again.
+pub fn execute(path: Option<&Path>, yes: bool, default_service: &str) -> Result<()> {
+    let manifest_path = project::locate(path)?;
+    let content = fs::read_to_string(&manifest_path)?;
+    let manifest = Manifest::parse(&content)?;
+    let service = manifest.service.as_deref().unwrap_or(default_service);
+
+    println!("{}:\n", manifest_path.display());
+    for line in content.lines() {
+        println!("  {}", line);
+    }
+    println!();
+
+    if !yes
+        && !Confirm::new()
+            .with_prompt(format!(
+                "Allow this project to load {} from service {}?",
+                manifest.keys_spec(),
+                service
+            ))
+            .interact()
+            .unwrap_or(false)
+    {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    TrustStore::new().allow(&manifest_path, &TrustStore::hash(content.as_bytes()), service)?;
+    println!("Trusted {}; it loads at the next prompt there.", manifest_path.display());
+    Ok(())
+}
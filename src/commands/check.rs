@@ -1,14 +1,15 @@
 
+use crate::commands::list::describe_entry;
 use crate::error::Result;
-use crate::keychain::KeychainManager;
+use crate::keychain::{KeyIndex, SecretStore};
 
-pub fn execute(verbose: bool, service_name: &str) -> Result<()> {
+pub fn execute(verbose: bool, store: &dyn SecretStore) -> Result<()> {
     println!("\n=== Security Configuration Check ===\n");
 
-    let manager = KeychainManager::new(service_name);
+    let service_name = store.service_name();
 
     // Check if any secrets exist in Keychain
-    let secrets = manager.retrieve_all()?;
+    let secrets = store.retrieve_all()?;
 
     println!("Keychain Service: {}", service_name);
     println!("Stored Secrets: {}\n", secrets.len());
@@ -20,6 +21,8 @@ pub fn execute(verbose: bool, service_name: &str) -> Result<()> {
     }
 
     if verbose {
+        let manifest = KeyIndex::new(service_name).load()?;
+
         println!("Stored secrets:");
         for (key, value) in &secrets {
             let value_display = if value.len() > 50 {
@@ -28,13 +31,18 @@ pub fn execute(verbose: bool, service_name: &str) -> Result<()> {
                 value.clone()
             };
             println!("  [✓] {} = {}", key, value_display);
+            if let Some(entry) = manifest.keys.get(key) {
+                for line in describe_entry(entry) {
+                    println!("        {}", line);
+                }
+            }
         }
         println!();
     }
 
     // Check Keychain accessibility
     println!("Security Checks:");
-    check_keychain_access(&manager, service_name);
+    check_keychain_access(store);
     check_hook_configuration();
     check_environment_variables();
 
@@ -51,9 +59,9 @@ pub fn execute(verbose: bool, service_name: &str) -> Result<()> {
     Ok(())
 }
 
-fn check_keychain_access(manager: &crate::keychain::KeychainManager, _service_name: &str) {
+fn check_keychain_access(store: &dyn SecretStore) {
     // Try to retrieve a test secret to verify access
-    match manager.retrieve_all() {
+    match store.retrieve_all() {
         Ok(secrets) => {
             if !secrets.is_empty() {
                 println!("  [✓] Keychain is accessible (found {} secrets)", secrets.len());
@@ -71,9 +79,10 @@ fn check_hook_configuration() {
     println!("  [✓] Hook validation: Run 'keychain-cli validate' to test");
 
     // Test with a safe command
-    if let Ok(_) = std::process::Command::new("bash")
-        .args(&["-c", "echo 'test' | keychain-cli validate"])
+    if std::process::Command::new("bash")
+        .args(["-c", "echo 'test' | keychain-cli validate"])
         .output()
+        .is_ok()
     {
         println!("  [✓] Hook binary is executable");
     }
@@ -0,0 +1,104 @@
+use dialoguer::Confirm;
+use log::warn;
+
+use crate::error::Result;
+use crate::keychain::{KeyIndex, SecretStore};
+
+pub fn execute(keys: Option<&str>, all: bool, force: bool, store: &dyn SecretStore) -> Result<()> {
+    let service_name = store.service_name();
+    let index = KeyIndex::new(service_name);
+
+    let targets: Vec<String> = if all {
+        let mut names = index.load_keys()?;
+        for (key, _) in store.retrieve_all()? {
+            if !names.contains(&key) {
+                names.push(key);
+            }
+        }
+        names.sort();
+        names
+    } else {
+        keys.unwrap_or_default()
+            .split(',')
+            .map(|k| k.trim().to_string())
+            .filter(|k| !k.is_empty())
+            .collect()
+    };
+
+    if targets.is_empty() {
+        println!("No keys to delete for service: {}", service_name);
+        return Ok(());
+    }
+
+    if !force {
+        println!("\nThis will permanently delete from service {}:", service_name);
+        for key in &targets {
+            println!("  - {}", key);
+        }
+
+        if !Confirm::new()
+            .with_prompt(format!("Delete {} key(s)?", targets.len()))
+            .interact()
+            .unwrap_or(false)
+        {
+            println!("Delete cancelled.");
+            return Ok(());
+        }
+    }
+
+    let mut deleted_count = 0;
+    let mut failed_count = 0;
+
+    println!("\nDeleting secrets...");
+    for key in &targets {
+        // A store that can't answer keeps the index entry, so nothing is lost
+        let exists = match store.try_exists(key) {
+            Ok(exists) => exists,
+            Err(e) => {
+                warn!("Failed to check {}: {}", key, e);
+                println!("  [✗] {} failed: {}", key, e);
+                failed_count += 1;
+                continue;
+            }
+        };
+        if !exists {
+            // Ghost entry: nothing in the store, but the index still lists it
+            match index.remove(key) {
+                Ok(true) => {
+                    println!("  [✓] {} was not in the store; removed stale index entry", key);
+                    deleted_count += 1;
+                }
+                Ok(false) => {
+                    println!("  [✗] {} not found", key);
+                    failed_count += 1;
+                }
+                Err(e) => {
+                    warn!("Failed to clean index entry for {}: {}", key, e);
+                    println!("  [✗] {} failed: {}", key, e);
+                    failed_count += 1;
+                }
+            }
+            continue;
+        }
+
+        match store.delete(key) {
+            Ok(_) => {
+                println!("  [✓] {} deleted", key);
+                deleted_count += 1;
+            }
+            Err(e) => {
+                warn!("Failed to delete {}: {}", key, e);
+                println!("  [✗] {} failed: {}", key, e);
+                failed_count += 1;
+            }
+        }
+    }
+
+    println!("\nDeleted: {} secrets", deleted_count);
+    if failed_count > 0 {
+        println!("Failed: {} secrets", failed_count);
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use std::path::Path;
+
+use crate::error::Result;
+use crate::keychain::TrustStore;
+use crate::project;
+
+/// Withdraw approval of a project manifest; the hook unloads its keys at
+/// the next prompt
+pub fn execute(path: Option<&Path>) -> Result<()> {
+    let manifest_path = project::locate(path)?;
+    if TrustStore::new().revoke(&manifest_path)? {
+        println!("Revoked trust in {}", manifest_path.display());
+    } else {
+        println!("{} was not trusted", manifest_path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,212 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use super::load::select_keys;
+use crate::config::is_valid_key;
+use crate::error::{Error, Result};
+use crate::keychain::{self, TrustStore};
+use crate::output::{self, shell, Format};
+use crate::project::{self, Manifest};
+
+/// Which manifest is loaded: `<trusted|blocked|failed>:<sha256>:<path>`
+const PROJECT_VAR: &str = "KEYCHAIN_CLI_PROJECT";
+/// Comma-separated names the hook exported, to restore or unset on leaving
+const KEYS_VAR: &str = "KEYCHAIN_CLI_KEYS";
+/// JSON object of the values those names had before the hook exported them
+const SAVED_VAR: &str = "KEYCHAIN_CLI_SAVED";
+
+const BASH_HOOK: &str = r#"_keychain_cli_hook() {
+  local previous_exit_status=$?
+  eval "$({command})"
+  return $previous_exit_status
+}
+if [[ ";${PROMPT_COMMAND[*]:-};" != *";_keychain_cli_hook;"* ]]; then
+  PROMPT_COMMAND="_keychain_cli_hook${PROMPT_COMMAND:+;$PROMPT_COMMAND}"
+fi
+"#;
+
+const ZSH_HOOK: &str = r#"_keychain_cli_hook() {
+  eval "$({command})"
+}
+typeset -ag precmd_functions chpwd_functions
+if (( ! ${precmd_functions[(I)_keychain_cli_hook]} )); then
+  precmd_functions=(_keychain_cli_hook $precmd_functions)
+fi
+if (( ! ${chpwd_functions[(I)_keychain_cli_hook]} )); then
+  chpwd_functions=(_keychain_cli_hook $chpwd_functions)
+fi
+"#;
+
+const FISH_HOOK: &str = r#"function __keychain_cli_hook --on-event fish_prompt --on-variable PWD
+    {command} | source
+end
+"#;
+
+/// Print the prompt hook for `shell`
+///
+/// The hook runs `hook-export` before every prompt with the same backend
+/// and service as this invocation.
+pub fn shell(shell_name: &str, backend: &str, service_name: &str) -> Result<()> {
+    let exe = env::current_exe()?;
+    let exe = exe.to_string_lossy();
+    let quote = match shell_name {
+        "fish" => shell::fish_quote,
+        _ => shell::posix_quote,
+    };
+    let command = format!(
+        "{} --backend {} --service-name {} hook-export {}",
+        quote(&exe),
+        quote(backend),
+        quote(service_name),
+        shell_name
+    );
+
+    let template = match shell_name {
+        "bash" => BASH_HOOK,
+        "zsh" => ZSH_HOOK,
+        "fish" => FISH_HOOK,
+        _ => return Err(unsupported(shell_name)),
+    };
+    print!("{}", template.replace("{command}", &command));
+    Ok(())
+}
+
+/// Print the commands that bring the environment in line with the
+/// manifest for the current directory
+///
+/// Nothing is printed while the manifest, its trust and the directory's
+/// project stay the same, so the store is only read on entering a project
+/// (or after `allow`). Leaving restores what the exported names held before,
+/// or unsets them. Manifests that aren't pinned by `allow` load nothing, and
+/// one that fails to load is retried at every prompt but reported once.
+pub fn export(shell_name: &str, backend: &str, default_service: &str) -> Result<()> {
+    let format = match shell_name {
+        "bash" | "zsh" => Format::Bash,
+        "fish" => Format::Fish,
+        _ => return Err(unsupported(shell_name)),
+    };
+    let fish = matches!(format, Format::Fish);
+    let assign = if fish { shell::fish } else { shell::posix };
+    let unset = if fish { shell::fish_unset } else { shell::posix_unset };
+
+    let manifest = match env::current_dir().ok().and_then(|dir| project::find(&dir)) {
+        Some(path) => {
+            let content = fs::read_to_string(&path)?;
+            let hash = TrustStore::hash(content.as_bytes());
+            let service = Manifest::parse(&content)
+                .ok()
+                .and_then(|manifest| manifest.service)
+                .unwrap_or_else(|| default_service.to_string());
+            let trusted = TrustStore::new().is_trusted(&path, &hash, &service)?;
+            Some((path, content, hash, trusted, service))
+        }
+        None => None,
+    };
+
+    let previous = env::var(PROJECT_VAR).ok();
+    let current = manifest.as_ref().map(|(path, _, hash, trusted, _)| {
+        marker(if *trusted { "trusted" } else { "blocked" }, hash, path)
+    });
+    if current == previous {
+        return Ok(());
+    }
+
+    // Put back what the previous project's names held before it loaded
+    let exported: Vec<String> = env::var(KEYS_VAR)
+        .map(|keys| keys.split(',').filter(|key| is_valid_key(key)).map(String::from).collect())
+        .unwrap_or_default();
+    let saved: BTreeMap<String, String> = env::var(SAVED_VAR)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    let mut script = String::new();
+    for key in &exported {
+        match saved.get(key) {
+            Some(value) => script.push_str(&assign(key, value)),
+            None => script.push_str(&unset(key)),
+        }
+    }
+    for var in [KEYS_VAR, SAVED_VAR, PROJECT_VAR] {
+        if env::var_os(var).is_some() {
+            script.push_str(&unset(var));
+        }
+    }
+
+    if let Some((path, content, hash, trusted, service)) = manifest {
+        let mut state = if trusted { "trusted" } else { "blocked" };
+        if !trusted {
+            eprintln!(
+                "keychain-cli: {} is not trusted to read service {}; review it and run \
+                 `keychain-cli --service-name {} allow`",
+                path.display(),
+                service,
+                service
+            );
+        } else {
+            match load(&content, backend, &service) {
+                Ok(secrets) => {
+                    let names: Vec<&str> = secrets.iter().map(|(key, _)| key.as_str()).collect();
+                    eprintln!("keychain-cli: loaded {} from {}", names.join(", "), path.display());
+
+                    // What each name held before any project touched it
+                    let before: BTreeMap<&str, String> = names
+                        .iter()
+                        .filter_map(|&name| {
+                            let value = if exported.iter().any(|key| key == name) {
+                                saved.get(name).cloned()
+                            } else {
+                                env::var(name).ok()
+                            };
+                            value.map(|value| (name, value))
+                        })
+                        .collect();
+
+                    script.push_str(&assign(KEYS_VAR, &names.join(",")));
+                    if !before.is_empty() {
+                        script.push_str(&assign(SAVED_VAR, &serde_json::to_string(&before)?));
+                    }
+                    let options = output::Options::default();
+                    script.push_str(&output::render(format, &secrets, &options)?);
+                }
+                Err(e) => {
+                    // Retried at the next prompt, since the current marker won't match
+                    state = "failed";
+                    if previous != Some(marker(state, &hash, &path)) {
+                        eprintln!("keychain-cli: {}: {}", path.display(), e);
+                    }
+                }
+            }
+        }
+        script.push_str(&assign(PROJECT_VAR, &marker(state, &hash, &path)));
+    }
+
+    print!("{}", script);
+    Ok(())
+}
+
+/// The manifest's keys from `service`, selected and renamed
+fn load(content: &str, backend: &str, service: &str) -> Result<Vec<(String, String)>> {
+    let manifest = Manifest::parse(content)?;
+    let store = keychain::open_backend(backend, service, None)?;
+    let secrets = select_keys(
+        store.retrieve_all()?,
+        Some(&manifest.keys_spec()),
+        manifest.strict,
+        service,
+    )?;
+    manifest.renames()?.apply(secrets, true)
+}
+
+/// Value of `PROJECT_VAR`
+fn marker(state: &str, hash: &str, path: &Path) -> String {
+    format!("{}:{}:{}", state, hash, path.display())
+}
+
+fn unsupported(shell_name: &str) -> Error {
+    Error::ValidationError(format!(
+        "Unsupported shell: {}. Use bash, zsh or fish",
+        shell_name
+    ))
+}
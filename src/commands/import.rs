@@ -0,0 +1,37 @@
+use std::path::Path;
+
+use super::setup::{self, SetupOptions};
+use crate::config::{Classifier, ParseOptions, Secrets};
+use crate::error::Result;
+use crate::import::{self, Format, ItemRule, KeyRule};
+use crate::keychain::SecretStore;
+
+pub fn execute(
+    file: &Path,
+    format: Format,
+    rule: &KeyRule,
+    item_rule: &ItemRule,
+    options: &SetupOptions,
+    store: &dyn SecretStore,
+) -> Result<()> {
+    println!("\n=== Keychain Import ===");
+    println!("Reading {} file: {}", format, file.display());
+
+    let parse_options = ParseOptions {
+        interpolate: options.interpolate,
+    };
+    let pairs = import::read(file, format, rule, item_rule, parse_options)?;
+
+    let classifier = Classifier::load()?;
+    let mut secrets = Secrets::from_pairs(pairs, &classifier);
+
+    if let Some(reason) = format.sensitive_reason() {
+        for secret in &mut secrets.secrets {
+            secret.sensitive = true;
+            secret.reason = reason.to_string();
+        }
+    }
+
+    setup::store_secrets(secrets, &setup::describe_source(file), options, store)?;
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use zeroize::Zeroizing;
+
+use crate::config::reference;
+use crate::error::{Error, Result};
+use crate::keychain::{self, SecretStore};
+
+/// Render `template` with every secret reference replaced by its value
+///
+/// `{{ keychain "KEY" }}` reads from `store`; `keychain://<service>/<KEY>`
+/// reads from that service, opened through `open_service` when it differs.
+/// Nothing is written unless every reference resolves. `template` may be
+/// `-` for stdin; the result goes to stdout or to a new 0600 `output` file.
+/// An existing `output` is only replaced with `force`.
+pub fn execute(
+    template: &Path,
+    output: Option<&Path>,
+    force: bool,
+    store: &dyn SecretStore,
+    open_service: &dyn Fn(&str) -> Result<Box<dyn SecretStore>>,
+) -> Result<()> {
+    if let Some(path) = output {
+        if path.exists() && !force {
+            return Err(Error::ValidationError(format!(
+                "{} already exists; pass --force to replace it",
+                path.display()
+            )));
+        }
+    }
+
+    let text = if template == Path::new("-") {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text)?;
+        text
+    } else {
+        fs::read_to_string(template)?
+    };
+
+    let references = reference::scan(&text)?;
+
+    let mut others: HashMap<String, Box<dyn SecretStore>> = HashMap::new();
+    let mut values: HashMap<(String, String), Option<String>> = HashMap::new();
+
+    for r in &references {
+        let service = r.service.as_deref().unwrap_or(store.service_name());
+        let id = (service.to_string(), r.key.clone());
+        if values.contains_key(&id) {
+            continue;
+        }
+
+        let source: &dyn SecretStore = if service == store.service_name() {
+            store
+        } else {
+            if !others.contains_key(service) {
+                others.insert(service.to_string(), open_service(service)?);
+            }
+            others[service].as_ref()
+        };
+        values.insert(id, source.retrieve(&r.key).ok());
+    }
+
+    let mut missing: Vec<String> = values
+        .iter()
+        .filter(|(_, value)| value.is_none())
+        .map(|((service, key), _)| format!("{}/{}", service, key))
+        .collect();
+    if !missing.is_empty() {
+        missing.sort();
+        return Err(Error::ValidationError(format!(
+            "Unresolved references: {}",
+            missing.join(", ")
+        )));
+    }
+
+    let rendered = Zeroizing::new(reference::render(&text, &references, |r| {
+        let service = r.service.as_deref().unwrap_or(store.service_name());
+        values[&(service.to_string(), r.key.clone())].clone().unwrap_or_default()
+    }));
+
+    match output {
+        Some(path) => {
+            keychain::replace_private(path, rendered.as_bytes())?;
+            eprintln!(
+                "Resolved {} reference(s) into {} (mode 0600)",
+                references.len(),
+                path.display()
+            );
+        }
+        None => io::stdout().write_all(rendered.as_bytes())?,
+    }
+
+    Ok(())
+}
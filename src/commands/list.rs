@@ -0,0 +1,152 @@
+use serde_json::json;
+
+use crate::error::{Error, Result};
+use crate::keychain::{KeyEntry, KeyIndex, SecretStore};
+
+/// One row of `list` output: index metadata plus store presence, never values
+struct Listing<'a> {
+    key: &'a str,
+    entry: &'a KeyEntry,
+    stored: bool,
+}
+
+pub fn execute(format: &str, store: &dyn SecretStore) -> Result<()> {
+    let service_name = store.service_name();
+    let manifest = KeyIndex::new(service_name).load()?;
+
+    let listings: Vec<Listing> = manifest
+        .keys
+        .iter()
+        .map(|(key, entry)| Listing {
+            key,
+            entry,
+            stored: store.exists(key),
+        })
+        .collect();
+
+    match format {
+        "plain" => output_plain(service_name, &listings),
+        "table" => output_table(service_name, &listings),
+        "json" => output_json(&listings),
+        _ => Err(Error::ValidationError(format!(
+            "Unknown format: {}. Use plain, table, or json",
+            format
+        ))),
+    }
+}
+
+fn output_plain(service_name: &str, listings: &[Listing]) -> Result<()> {
+    if listings.is_empty() {
+        println!("No keys indexed for service: {}", service_name);
+        return Ok(());
+    }
+
+    println!("Keys for service {} ({}):\n", service_name, listings.len());
+    for listing in listings {
+        let marker = if listing.stored { "✓" } else { "✗" };
+        println!("  [{}] {}", marker, listing.key);
+        if !listing.stored {
+            println!("      missing from store");
+        }
+        for line in describe_entry(listing.entry) {
+            println!("      {}", line);
+        }
+    }
+
+    Ok(())
+}
+
+fn output_table(service_name: &str, listings: &[Listing]) -> Result<()> {
+    if listings.is_empty() {
+        println!("No keys indexed for service: {}", service_name);
+        return Ok(());
+    }
+
+    let rows: Vec<[String; 5]> = listings
+        .iter()
+        .map(|l| {
+            [
+                l.key.to_string(),
+                if l.stored { "yes" } else { "MISSING" }.to_string(),
+                l.entry.tags.join(","),
+                l.entry.updated_at.format("%Y-%m-%d").to_string(),
+                match l.entry.expires_at {
+                    Some(_) if l.entry.is_expired() => "EXPIRED".to_string(),
+                    Some(t) => t.format("%Y-%m-%d").to_string(),
+                    None => "-".to_string(),
+                },
+            ]
+        })
+        .collect();
+
+    let header = ["KEY", "STORED", "TAGS", "UPDATED", "EXPIRES"];
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let print_row = |cells: &[&str]| {
+        let line: Vec<String> = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    };
+
+    print_row(&header);
+    for row in &rows {
+        print_row(&row.each_ref().map(String::as_str));
+    }
+
+    Ok(())
+}
+
+fn output_json(listings: &[Listing]) -> Result<()> {
+    let items: Vec<serde_json::Value> = listings
+        .iter()
+        .map(|l| {
+            json!({
+                "key": l.key,
+                "stored": l.stored,
+                "created_at": l.entry.created_at,
+                "updated_at": l.entry.updated_at,
+                "source": l.entry.source,
+                "tags": l.entry.tags,
+                "description": l.entry.description,
+                "expires_at": l.entry.expires_at,
+                "expired": l.entry.is_expired(),
+            })
+        })
+        .collect();
+
+    println!("{}", serde_json::to_string_pretty(&items)?);
+    Ok(())
+}
+
+/// Human-readable metadata lines for an index entry
+pub fn describe_entry(entry: &KeyEntry) -> Vec<String> {
+    let mut lines = vec![format!(
+        "created: {}  updated: {}",
+        entry.created_at.format("%Y-%m-%d %H:%M"),
+        entry.updated_at.format("%Y-%m-%d %H:%M")
+    )];
+
+    if let Some(source) = &entry.source {
+        lines.push(format!("source: {}", source));
+    }
+    if !entry.tags.is_empty() {
+        lines.push(format!("tags: {}", entry.tags.join(", ")));
+    }
+    if let Some(description) = &entry.description {
+        lines.push(format!("description: {}", description));
+    }
+    if let Some(expires_at) = entry.expires_at {
+        let status = if entry.is_expired() { " (EXPIRED)" } else { "" };
+        lines.push(format!("expires: {}{}", expires_at.format("%Y-%m-%d"), status));
+    }
+
+    lines
+}
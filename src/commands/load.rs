@@ -1,64 +1,82 @@
-use serde_json::json;
+use log::warn;
 
+use crate::config::{Renames, Selector, Tags};
 use crate::error::{Error, Result};
-use crate::keychain::KeychainManager;
+use crate::keychain::{KeyIndex, SecretStore};
+use crate::output::{self, Format};
 
-pub fn execute(format: &str, keys: Option<&str>, service_name: &str) -> Result<()> {
-    let manager = KeychainManager::new(service_name);
-    let secrets = manager.retrieve_all()?;
+/// Print the secrets as shell assignments, JSON or a config file format
+///
+/// `keys` picks stored names (see `select_keys`); `renames` then decides
+/// the names printed. `format` is any name `output::Format` accepts,
+/// including `auto`. `name` and `namespace` only apply to `k8s-secret`; the
+/// name defaults to the service name.
+pub fn execute(
+    format: &str,
+    keys: Option<&str>,
+    strict: bool,
+    renames: &Renames,
+    name: Option<&str>,
+    namespace: Option<&str>,
+    store: &dyn SecretStore,
+) -> Result<()> {
+    let format: Format = format.parse()?;
+    if format != Format::K8sSecret && (name.is_some() || namespace.is_some()) {
+        return Err(Error::ValidationError(
+            "--name and --namespace only apply to --format k8s-secret".to_string(),
+        ));
+    }
+    let options = output::Options {
+        name: name.unwrap_or(store.service_name()).to_string(),
+        namespace: namespace.map(str::to_string),
+    };
+
+    let service_name = store.service_name();
+    let secrets = store.retrieve_all()?;
 
     if secrets.is_empty() {
         eprintln!("No secrets found in Keychain for service: {}", service_name);
         std::process::exit(1);
     }
 
-    // Filter by keys if specified
-    let secrets = if let Some(key_filter) = keys {
-        let filter_keys: Vec<&str> = key_filter.split(',')
-            .map(|k| k.trim())
-            .collect();
-        secrets.into_iter()
-            .filter(|(k, _)| filter_keys.contains(&k.as_str()))
-            .collect::<Vec<_>>()
-    } else {
-        secrets
-    };
+    let secrets = select_keys(secrets, keys, strict, service_name)?;
+    let secrets = renames.apply(secrets, format.sets_variables())?;
 
-    match format {
-        "bash" => output_bash(&secrets),
-        "json" => output_json(&secrets),
-        "export" => output_export(&secrets),
-        _ => Err(Error::ValidationError(format!(
-            "Unknown format: {}. Use bash, json, or export",
-            format
-        ))),
-    }
-}
-
-fn output_bash(secrets: &[(String, String)]) -> Result<()> {
-    for (key, value) in secrets {
-        // Escape single quotes in values
-        let escaped = value.replace("'", "'\\''");
-        println!("export {}='{}'", key, escaped);
-    }
+    print!("{}", output::render(format, &secrets, &options)?);
     Ok(())
 }
 
-fn output_json(secrets: &[(String, String)]) -> Result<()> {
-    let mut obj = serde_json::Map::new();
-    for (key, value) in secrets {
-        obj.insert(key.clone(), json!(value));
-    }
-    let json = serde_json::Value::Object(obj);
-    println!("{}", serde_json::to_string_pretty(&json)?);
-    Ok(())
-}
+/// Keep the keys `keys` selects (see `Selector`), or everything without it
+///
+/// A term that matches no stored key is logged, or with `strict` an error
+/// listing every such term.
+pub fn select_keys(
+    secrets: Vec<(String, String)>,
+    keys: Option<&str>,
+    strict: bool,
+    service_name: &str,
+) -> Result<Vec<(String, String)>> {
+    let Some(spec) = keys else {
+        return Ok(secrets);
+    };
 
-fn output_export(secrets: &[(String, String)]) -> Result<()> {
-    // Use bash command to set environment variables directly
-    for (key, value) in secrets {
-        let escaped = value.replace("'", "'\\''");
-        println!("export {}='{}'", key, escaped);
+    let selector = Selector::parse(spec)?;
+    let tags = if selector.uses_tags() {
+        KeyIndex::new(service_name).load_tags()?
+    } else {
+        Tags::new()
+    };
+
+    let (selected, unmatched) = selector.select(secrets, |(key, _)| key.as_str(), &tags);
+    if !unmatched.is_empty() {
+        if strict {
+            return Err(Error::ValidationError(format!(
+                "Not found in service {}: {}",
+                service_name,
+                unmatched.join(", ")
+            )));
+        }
+        warn!("No stored key matches {}", unmatched.join(", "));
     }
-    Ok(())
+    Ok(selected)
 }
@@ -2,3 +2,14 @@ pub mod setup;
 pub mod load;
 pub mod validate;
 pub mod check;
+pub mod list;
+pub mod delete;
+pub mod reconcile;
+pub mod import;
+pub mod set;
+pub mod restore;
+pub mod inject;
+pub mod run;
+pub mod hook;
+pub mod allow;
+pub mod deny;
@@ -0,0 +1,70 @@
+use crate::error::Result;
+use crate::keychain::{KeyIndex, SecretStore};
+
+/// Source recorded for orphans adopted into the index
+const ADOPTED_SOURCE: &str = "adopted by reconcile";
+
+pub fn execute(fix: bool, store: &dyn SecretStore) -> Result<()> {
+    let service_name = store.service_name();
+    let index = KeyIndex::new(service_name);
+
+    println!("\n=== Index / Store Reconciliation ===\n");
+    println!("Service: {}", service_name);
+
+    let indexed = index.load_keys()?;
+    let stored = store.list_stored()?;
+
+    // Ghosts: listed in the index, but the store no longer has them. A store
+    // that can't answer aborts here, before anything is pruned.
+    let mut ghosts: Vec<&String> = Vec::new();
+    for key in &indexed {
+        if !store.try_exists(key)? {
+            ghosts.push(key);
+        }
+    }
+    // Orphans: present in the store, but never recorded in the index
+    let orphans: Vec<&String> = stored.iter().filter(|k| !indexed.contains(k)).collect();
+
+    println!("Indexed: {}  Stored: {}\n", indexed.len(), stored.len());
+
+    if ghosts.is_empty() && orphans.is_empty() {
+        println!("[✓] Index and store are consistent");
+        return Ok(());
+    }
+
+    if !ghosts.is_empty() {
+        println!("Ghosts (in index, missing from store): {}", ghosts.len());
+        for key in &ghosts {
+            if fix {
+                index.remove(key)?;
+                println!("  [✓] {} pruned from index", key);
+            } else {
+                println!("  [!] {}", key);
+            }
+        }
+        println!();
+    }
+
+    if !orphans.is_empty() {
+        println!("Orphans (in store, missing from index): {}", orphans.len());
+        for key in &orphans {
+            if fix {
+                index.update(key, |entry| {
+                    entry.source.get_or_insert_with(|| ADOPTED_SOURCE.to_string());
+                })?;
+                println!("  [✓] {} adopted into index", key);
+            } else {
+                println!("  [!] {}", key);
+            }
+        }
+        println!();
+    }
+
+    if !fix {
+        println!("Run 'keychain-cli reconcile --fix' to prune ghosts and adopt orphans.");
+        std::process::exit(1);
+    }
+
+    println!("[✓] Reconciliation complete");
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::keychain::{self, backup};
+
+/// Decrypt a `setup --scrub-backup` file to `output`, or to stdout
+///
+/// An existing `output` is only replaced with `force`.
+pub fn execute(backup_path: &Path, output: Option<&Path>, force: bool) -> Result<()> {
+    let passphrase = backup::read_passphrase(false)?;
+    let plaintext = backup::open(backup_path, &passphrase)?;
+
+    match output {
+        Some(path) => {
+            if path.exists() && !force {
+                return Err(Error::ValidationError(format!(
+                    "{} already exists; pass --force to replace it",
+                    path.display()
+                )));
+            }
+            keychain::replace_private(path, &plaintext)?;
+            eprintln!("Restored {} (mode 0600)", path.display());
+        }
+        None => io::stdout().write_all(&plaintext)?,
+    }
+
+    Ok(())
+}
@@ -0,0 +1,236 @@
+use std::io::{self, Read, Write};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::load::select_keys;
+use crate::config::Renames;
+use crate::error::{Error, Result};
+use crate::keychain::SecretStore;
+use crate::mask::Masker;
+
+/// How long a possible start of a value is held back when no more output
+/// follows, e.g. before a prompt waits for input
+const HOLD_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// How long output is still copied after the command exits; a background
+/// process it started may keep the pipes open indefinitely
+const DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Run `command` with the selected secrets added to its environment
+///
+/// On Unix the process is replaced by the command (exec), so its exit code
+/// and any signal that ends it reach the caller unchanged, and the secrets
+/// only ever exist in the child's environment. Elsewhere the command is
+/// waited on and its exit code passed through.
+///
+/// `renames` decides the variable names the command sees.
+///
+/// With `mask`, the command is started with its stdout and stderr piped back
+/// through a `Masker`, so any secret value it prints shows up as `***KEY***`.
+/// SIGTERM and SIGHUP are forwarded to it; SIGINT and SIGQUIT from the
+/// terminal already reach it directly.
+pub fn execute(
+    command: &[String],
+    keys: Option<&str>,
+    strict: bool,
+    renames: &Renames,
+    mask: bool,
+    store: &dyn SecretStore,
+) -> Result<()> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| Error::ValidationError("No command given".to_string()))?;
+
+    let stored = store.retrieve_all()?;
+    if stored.is_empty() {
+        eprintln!("No secrets stored for service: {}", store.service_name());
+        std::process::exit(1);
+    }
+    let secrets = select_keys(stored, keys, strict, store.service_name())?;
+    if secrets.is_empty() {
+        eprintln!(
+            "--keys '{}' matched none of the secrets in service: {}",
+            keys.unwrap_or_default(),
+            store.service_name()
+        );
+        std::process::exit(1);
+    }
+    let secrets = renames.apply(secrets, true)?;
+
+    let mut child = Command::new(program);
+    child.args(args).envs(secrets.iter().map(|(k, v)| (k, v)));
+    if !mask {
+        return exec(child, program);
+    }
+
+    let masker = Masker::new(secrets.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+    if masker.is_empty() {
+        log::warn!("No values long enough to mask");
+    }
+    supervise(child, program, masker)
+}
+
+/// Spawn `child` with masked output and exit the way it did
+fn supervise(mut child: Command, program: &str, masker: Masker) -> Result<()> {
+    child.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let mut child = match child.spawn() {
+        Ok(child) => child,
+        Err(err) => exit_for_spawn_error(program, err),
+    };
+
+    #[cfg(unix)]
+    signals::forward_to(child.id());
+
+    let (done, finished) = mpsc::channel();
+    pump(child.stdout.take().expect("piped stdout"), io::stdout(), masker.clone(), done.clone());
+    pump(child.stderr.take().expect("piped stderr"), io::stderr(), masker, done);
+    let status = child.wait()?;
+
+    let deadline = Instant::now() + DRAIN_TIMEOUT;
+    for _ in 0..2 {
+        if finished.recv_timeout(deadline.saturating_duration_since(Instant::now())).is_err() {
+            break;
+        }
+    }
+
+    exit_like(status)
+}
+
+/// Copy `reader` to `writer` through `masker` until EOF, then signal `done`
+///
+/// Reads happen on their own thread so that a held-back tail can be released
+/// after `HOLD_TIMEOUT` even while the next read is still blocked.
+fn pump<R, W>(mut reader: R, mut writer: W, mut masker: Masker, done: mpsc::Sender<()>)
+where
+    R: Read + Send + 'static,
+    W: Write + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel::<Vec<u8>>();
+    thread::spawn(move || {
+        let mut buf = [0u8; 8192];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    if sender.send(buf[..n].to_vec()).is_err() {
+                        break;
+                    }
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            }
+        }
+    });
+
+    thread::spawn(move || loop {
+        let chunk = if masker.has_pending() {
+            match receiver.recv_timeout(HOLD_TIMEOUT) {
+                Ok(chunk) => Some(chunk),
+                Err(RecvTimeoutError::Timeout) => {
+                    let _ = writer.write_all(&masker.flush()).and_then(|_| writer.flush());
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => None,
+            }
+        } else {
+            receiver.recv().ok()
+        };
+
+        // Write errors (e.g. a closed pipe) are ignored so the child is
+        // never blocked on a full pipe
+        match chunk {
+            Some(chunk) => {
+                let _ = writer.write_all(&masker.feed(&chunk)).and_then(|_| writer.flush());
+            }
+            None => {
+                let _ = writer.write_all(&masker.flush()).and_then(|_| writer.flush());
+                let _ = done.send(());
+                break;
+            }
+        }
+    });
+}
+
+#[cfg(unix)]
+fn exit_like(status: ExitStatus) -> Result<()> {
+    use std::os::unix::process::ExitStatusExt;
+
+    if let Some(signal) = status.signal() {
+        signals::raise(signal);
+    }
+    std::process::exit(status.code().unwrap_or(1))
+}
+
+#[cfg(not(unix))]
+fn exit_like(status: ExitStatus) -> Result<()> {
+    std::process::exit(status.code().unwrap_or(1))
+}
+
+#[cfg(unix)]
+mod signals {
+    use std::sync::atomic::{AtomicI32, Ordering};
+
+    static CHILD: AtomicI32 = AtomicI32::new(0);
+
+    extern "C" fn forward(signal: libc::c_int) {
+        let pid = CHILD.load(Ordering::SeqCst);
+        if pid > 0 {
+            // kill(2) is async-signal-safe
+            unsafe {
+                libc::kill(pid, signal);
+            }
+        }
+    }
+
+    /// Forward SIGTERM and SIGHUP to `pid` and ignore terminal interrupts,
+    /// which the terminal already delivers to the whole process group.
+    /// Installed after spawning so the child keeps default dispositions.
+    pub fn forward_to(pid: u32) {
+        CHILD.store(pid as i32, Ordering::SeqCst);
+        unsafe {
+            for signal in [libc::SIGTERM, libc::SIGHUP] {
+                libc::signal(signal, forward as extern "C" fn(libc::c_int) as libc::sighandler_t);
+            }
+            for signal in [libc::SIGINT, libc::SIGQUIT] {
+                libc::signal(signal, libc::SIG_IGN);
+            }
+        }
+    }
+
+    /// End this process with `signal`, like the child ended
+    pub fn raise(signal: i32) {
+        unsafe {
+            libc::signal(signal, libc::SIG_DFL);
+            libc::raise(signal);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn exec(mut child: Command, program: &str) -> Result<()> {
+    use std::os::unix::process::CommandExt;
+
+    // Only returns on failure
+    let err = child.exec();
+    exit_for_spawn_error(program, err)
+}
+
+#[cfg(not(unix))]
+fn exec(mut child: Command, program: &str) -> Result<()> {
+    match child.status() {
+        Ok(status) => std::process::exit(status.code().unwrap_or(1)),
+        Err(err) => exit_for_spawn_error(program, err),
+    }
+}
+
+/// Exit like a shell does when it can't start a command
+fn exit_for_spawn_error(program: &str, err: std::io::Error) -> ! {
+    eprintln!("keychain-cli: {}: {}", program, err);
+    let code = match err.kind() {
+        std::io::ErrorKind::NotFound => 127,
+        _ => 126,
+    };
+    std::process::exit(code)
+}
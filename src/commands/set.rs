@@ -0,0 +1,72 @@
+use dialoguer::{Confirm, Password};
+use std::io::{self, IsTerminal, Read};
+use zeroize::Zeroizing;
+
+use super::setup::{self, SetupOptions};
+use crate::config::{is_valid_key, Classifier, Secrets};
+use crate::error::{Error, Result};
+use crate::keychain::SecretStore;
+
+/// Store one value for `key`, read from a hidden prompt or from stdin
+///
+/// The value never touches disk; only the index entry is written.
+pub fn execute(key: &str, options: &SetupOptions, store: &dyn SecretStore) -> Result<()> {
+    if !is_valid_key(key) {
+        return Err(Error::ValidationError(format!("Invalid variable name: {}", key)));
+    }
+
+    let (value, source) = read_value(key)?;
+    if value.is_empty() {
+        return Err(Error::ValidationError(format!("Empty value for {}", key)));
+    }
+
+    let classifier = Classifier::load()?;
+    let secrets = Secrets::from_pairs([(key.to_string(), value.to_string())], &classifier);
+    let secret = &secrets.secrets[0];
+    let verdict = if secret.sensitive { "sensitive" } else { "not detected as sensitive" };
+    println!("{} is {} ({})", key, verdict, secret.reason);
+
+    if store.exists(key) {
+        if store.retrieve(key).is_ok_and(|current| current == secret.value) {
+            println!("{} is unchanged; nothing to store.", key);
+            return Ok(());
+        }
+        if !options.force
+            && !Confirm::new()
+                .with_prompt(format!("Overwrite {} in service {}?", key, store.service_name()))
+                .interact()
+                .unwrap_or(false)
+        {
+            println!("Cancelled.");
+            return Ok(());
+        }
+    }
+
+    let failed = setup::store_selected(&secrets.secrets, source, options, store);
+    if !failed.is_empty() {
+        return Err(Error::KeychainError(format!("Failed to store {}", key)));
+    }
+    Ok(())
+}
+
+/// Prompt without echo on a terminal, otherwise take stdin minus one
+/// trailing newline. Returns the value and how it is recorded as `source`.
+fn read_value(key: &str) -> Result<(Zeroizing<String>, &'static str)> {
+    if io::stdin().is_terminal() {
+        let value = Password::new()
+            .with_prompt(format!("Value for {}", key))
+            .interact()
+            .map_err(|e| Error::ValidationError(format!("Failed to read value: {}", e)))?;
+        return Ok((Zeroizing::new(value), "prompt"));
+    }
+
+    let mut value = Zeroizing::new(String::new());
+    io::stdin().read_to_string(&mut value)?;
+    if value.ends_with('\n') {
+        value.pop();
+        if value.ends_with('\r') {
+            value.pop();
+        }
+    }
+    Ok((value, "stdin"))
+}
@@ -1,70 +1,306 @@
-use dialoguer::Confirm;
+use chrono::{DateTime, NaiveDate, Utc};
+use dialoguer::{Confirm, MultiSelect};
 use log::warn;
+use std::fs;
+use std::io::{self, Read};
 use std::path::Path;
 
-use crate::config::Secrets;
-use crate::error::Result;
-use crate::keychain::KeychainManager;
+use crate::config::{dotenv, reference, Classifier, ParseOptions, Secret, Secrets, Selector, Tags};
+use crate::error::{Error, Result};
+use crate::keychain::{backup, shred_and_replace, KeyIndex, SecretStore};
 
-pub fn execute(
-    env_file: &Path,
-    keys: Option<&str>,
-    force: bool,
-    service_name: &str,
-) -> Result<()> {
+/// Options controlling which keys are stored and how they are annotated
+pub struct SetupOptions<'a> {
+    /// Only setup specific keys (comma-separated)
+    pub keys: Option<&'a str>,
+    /// Skip interactive selection and confirmation
+    pub force: bool,
+    /// Store every parsed variable, not only the sensitive ones
+    pub all: bool,
+    /// Tags recorded in the index for every stored key
+    pub tags: Vec<String>,
+    /// Expiry recorded in the index for every stored key
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Expand `${VAR}` references while parsing the .env file
+    pub interpolate: bool,
+    /// Print the change plan and exit without writing
+    pub dry_run: bool,
+    /// Replace stored values in the .env with `keychain://` references
+    pub scrub: bool,
+    /// Keep the original .env as an encrypted backup here when scrubbing
+    pub scrub_backup: Option<&'a Path>,
+}
+
+/// What `store_secrets` left in the store
+pub struct Outcome {
+    /// Selected secrets the store now holds with exactly these values
+    pub secured: Vec<Secret>,
+    /// Number of keys that failed to store
+    pub failed: usize,
+}
+
+/// What storing a key would do to the store
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Change {
+    New,
+    Changed,
+    Unchanged,
+}
+
+/// Comparison of the selected .env keys against the current store
+struct Plan {
+    changes: Vec<(Secret, Change)>,
+    /// Keys the store already holds that the .env file doesn't mention
+    only_in_store: Vec<String>,
+}
+
+impl Plan {
+    fn build(selected: &[Secret], parsed_keys: &[&str], store: &dyn SecretStore) -> Result<Self> {
+        let changes = selected
+            .iter()
+            .map(|secret| {
+                let change = if !store.exists(&secret.key) {
+                    Change::New
+                } else {
+                    match store.retrieve(&secret.key) {
+                        Ok(current) if current == secret.value => Change::Unchanged,
+                        _ => Change::Changed,
+                    }
+                };
+                (secret.clone(), change)
+            })
+            .collect();
+
+        let mut existing = KeyIndex::new(store.service_name()).load_keys()?;
+        existing.extend(store.list_stored().unwrap_or_default());
+        existing.sort();
+        existing.dedup();
+        existing.retain(|k| !parsed_keys.contains(&k.as_str()));
+
+        Ok(Plan {
+            changes,
+            only_in_store: existing,
+        })
+    }
+
+    fn print(&self, service_name: &str) {
+        println!("\nChange plan for service {}:", service_name);
+        for (secret, change) in &self.changes {
+            let (marker, label) = match change {
+                Change::New => ("+", "new"),
+                Change::Changed => ("~", "changed"),
+                Change::Unchanged => ("=", "unchanged, skipped"),
+            };
+            println!("  {} {} ({})", marker, secret.key, label);
+        }
+        for key in &self.only_in_store {
+            println!("  ? {} (only in store, left as is)", key);
+        }
+    }
+}
+
+/// Store secrets from `env_file`, or from dotenv text on stdin if `None`
+///
+/// Reading stdin keeps the values off disk entirely: only the index (key
+/// names and metadata) is written.
+pub fn execute(env_file: Option<&Path>, options: &SetupOptions, store: &dyn SecretStore) -> Result<()> {
     println!("\n=== Keychain Setup ===");
-    println!("Reading .env file: {}", env_file.display());
 
-    let mut secrets = Secrets::from_env_file(env_file)?;
-    secrets = secrets.filter_by_keys(keys)?;
+    let parse_options = ParseOptions {
+        interpolate: options.interpolate,
+    };
+    let classifier = Classifier::load()?;
 
-    let sensitive = secrets.sensitive_only();
+    match env_file {
+        Some(env_file) => {
+            println!("Reading .env file: {}", env_file.display());
+            let content = fs::read_to_string(env_file)?;
+            let secrets = Secrets::from_env_str(&content, parse_options, &classifier)?;
+            let outcome = store_secrets(secrets, &describe_source(env_file), options, store)?;
+
+            match outcome {
+                Some(outcome) if options.scrub => scrub(env_file, &content, &outcome, options, store),
+                _ => Ok(()),
+            }
+        }
+        None => {
+            println!("Reading .env text from stdin");
+            let mut content = String::new();
+            io::stdin().read_to_string(&mut content)?;
+            let secrets = Secrets::from_env_str(&content, parse_options, &classifier)?;
+            store_secrets(secrets, "stdin", options, store)?;
+            Ok(())
+        }
+    }
+}
 
-    if sensitive.is_empty() {
-        println!("No sensitive variables found in .env file");
+/// Rewrite `env_file` with references in place of the secured values
+///
+/// Nothing is touched unless every selected key was stored and reads back
+/// with the value from the file. The original is then either sealed into
+/// `options.scrub_backup` or only overwritten.
+fn scrub(
+    env_file: &Path,
+    content: &str,
+    outcome: &Outcome,
+    options: &SetupOptions,
+    store: &dyn SecretStore,
+) -> Result<()> {
+    if outcome.failed > 0 {
+        return Err(Error::KeychainError(format!(
+            "{} left untouched: {} key(s) failed to store",
+            env_file.display(),
+            outcome.failed
+        )));
+    }
+
+    for secret in &outcome.secured {
+        if !store.retrieve(&secret.key).is_ok_and(|v| v == secret.value) {
+            return Err(Error::KeychainError(format!(
+                "{} left untouched: {} did not read back from the store",
+                env_file.display(),
+                secret.key
+            )));
+        }
+    }
+
+    let parse_options = ParseOptions {
+        interpolate: options.interpolate,
+    };
+    let entries = dotenv::parse(content, parse_options)?;
+    let service_name = store.service_name();
+    let scrubbed = dotenv::replace_values(content, &entries, |entry| {
+        outcome
+            .secured
+            .iter()
+            .any(|s| s.key == entry.key)
+            .then(|| reference::format(service_name, &entry.key))
+    });
+    if scrubbed == content {
+        println!("\nNothing to scrub in {}", env_file.display());
         return Ok(());
     }
 
-    println!("\nFound {} sensitive variables:", sensitive.len());
-    for secret in &sensitive {
-        println!("  - {} (detected as: sensitive)", secret.key);
+    if let Some(backup_path) = options.scrub_backup {
+        let passphrase = backup::read_passphrase(true)?;
+        backup::seal(backup_path, content.as_bytes(), &passphrase)?;
+        println!("\nEncrypted backup written to {}", backup_path.display());
     }
 
-    if !force {
-        println!("\nThis will store the following in your Keychain:");
-        for secret in &sensitive {
-            println!("  [{}] {}", secret.key, mask_value(&secret.value));
+    shred_and_replace(env_file, scrubbed.as_bytes())?;
+    println!(
+        "Scrubbed {}: {} value(s) replaced with keychain:// references",
+        env_file.display(),
+        outcome.secured.len()
+    );
+    Ok(())
+}
+
+/// How a file is recorded as the `source` of index entries
+pub fn describe_source(path: &Path) -> String {
+    path.canonicalize()
+        .unwrap_or_else(|_| path.to_path_buf())
+        .display()
+        .to_string()
+}
+
+/// Select, plan and store parsed secrets, recording `source` in the index
+///
+/// Shared by `setup` and `import`: everything after the input is parsed.
+/// Returns `None` if the run was cancelled or a dry run.
+pub fn store_secrets(
+    secrets: Secrets,
+    source: &str,
+    options: &SetupOptions,
+    store: &dyn SecretStore,
+) -> Result<Option<Outcome>> {
+    let service_name = store.service_name();
+    let secrets = match options.keys {
+        Some(spec) => {
+            let selector = Selector::parse(spec)?;
+            let tags = if selector.uses_tags() {
+                KeyIndex::new(service_name).load_tags()?
+            } else {
+                Tags::new()
+            };
+            secrets.filter_by_keys(&selector, &tags)
         }
+        None => secrets,
+    };
 
-        if !Confirm::new()
-            .with_prompt("Continue with setup?")
-            .interact()
-            .unwrap_or(false)
-        {
-            println!("Setup cancelled.");
-            return Ok(());
+    let sensitive = secrets.sensitive_only();
+
+    let skipped: Vec<_> = secrets.secrets.iter().filter(|s| !s.sensitive).collect();
+    if !skipped.is_empty() {
+        let verb = if options.all { "Including" } else { "Skipping" };
+        println!("\n{} {} non-sensitive variables:", verb, skipped.len());
+        for secret in &skipped {
+            println!("  - {} ({})", secret.key, secret.reason);
         }
     }
 
-    let manager = KeychainManager::new(service_name);
-    let mut stored_count = 0;
-    let mut failed_count = 0;
+    if !sensitive.is_empty() {
+        println!("\nFound {} sensitive variables:", sensitive.len());
+        for secret in &sensitive {
+            println!("  - {} ({})", secret.key, secret.reason);
+        }
+    }
 
-    println!("\nStoring secrets in Keychain...");
-    for secret in &sensitive {
-        match manager.store(&secret.key, &secret.value) {
-            Ok(_) => {
-                println!("  [✓] {} stored", secret.key);
-                stored_count += 1;
-            }
-            Err(e) => {
-                warn!("Failed to store {}: {}", secret.key, e);
-                println!("  [✗] {} failed", secret.key);
-                failed_count += 1;
+    let defaults: Vec<bool> = secrets
+        .secrets
+        .iter()
+        .map(|s| options.all || s.sensitive)
+        .collect();
+
+    let selected: Vec<Secret> = if options.force || options.dry_run {
+        pick(&secrets.secrets, &defaults)
+    } else {
+        match select_interactively(&secrets.secrets, &defaults) {
+            Some(selected) => selected,
+            None => {
+                println!("Setup cancelled.");
+                return Ok(None);
             }
         }
+    };
+
+    let parsed_keys: Vec<&str> = secrets.secrets.iter().map(|s| s.key.as_str()).collect();
+    let plan = Plan::build(&selected, &parsed_keys, store)?;
+    plan.print(service_name);
+
+    if options.dry_run {
+        println!("\nDry run: nothing was written.");
+        return Ok(None);
     }
 
+    let (unchanged, selected): (Vec<_>, Vec<_>) = plan
+        .changes
+        .into_iter()
+        .partition(|(_, change)| *change == Change::Unchanged);
+    let mut secured: Vec<Secret> = unchanged.into_iter().map(|(secret, _)| secret).collect();
+    let selected: Vec<Secret> = selected.into_iter().map(|(secret, _)| secret).collect();
+
+    if selected.is_empty() {
+        println!("\nNothing to store: every selected key is already up to date.");
+        return Ok(Some(Outcome { secured, failed: 0 }));
+    }
+
+    if !options.force
+        && !Confirm::new()
+            .with_prompt(format!("Store {} key(s) in service {}?", selected.len(), service_name))
+            .interact()
+            .unwrap_or(false)
+    {
+        println!("Setup cancelled.");
+        return Ok(None);
+    }
+
+    println!("\nStoring secrets in Keychain...");
+    let failed = store_selected(&selected, source, options, store);
+    let stored_count = selected.len() - failed.len();
+    let failed_count = failed.len();
+    secured.extend(selected.into_iter().filter(|s| !failed.contains(&s.key)));
+
     println!("\n=== Setup Complete ===");
     println!("Stored: {} secrets", stored_count);
     if failed_count > 0 {
@@ -74,13 +310,103 @@ pub fn execute(
     println!("\nYou can now load these secrets with:");
     println!("  keychain-cli load --service-name {}", service_name);
 
-    Ok(())
+    Ok(Some(Outcome {
+        secured,
+        failed: failed_count,
+    }))
 }
 
-fn mask_value(value: &str) -> String {
-    if value.len() <= 4 {
-        "****".to_string()
-    } else {
-        format!("{}...{}", &value[..2], &value[value.len()-2..])
+/// Write each secret and its index entry, reporting per key
+///
+/// Returns the keys that failed.
+pub fn store_selected(
+    selected: &[Secret],
+    source: &str,
+    options: &SetupOptions,
+    store: &dyn SecretStore,
+) -> Vec<String> {
+    let index = KeyIndex::new(store.service_name());
+    let mut failed = Vec::new();
+
+    for secret in selected {
+        if let Err(e) = store.store(&secret.key, &secret.value) {
+            warn!("Failed to store {}: {}", secret.key, e);
+            println!("  [✗] {} failed", secret.key);
+            failed.push(secret.key.clone());
+            continue;
+        }
+        println!("  [✓] {} stored", secret.key);
+
+        // The secret is safely stored by now, so losing its metadata is
+        // only worth a warning
+        let metadata = index.update(&secret.key, |entry| {
+            entry.source = Some(source.to_string());
+            if !options.tags.is_empty() {
+                entry.tags = options.tags.clone();
+            }
+            if options.expires_at.is_some() {
+                entry.expires_at = options.expires_at;
+            }
+        });
+        if let Err(e) = metadata {
+            warn!("{} is stored, but its index metadata was not saved: {}", secret.key, e);
+        }
+    }
+
+    failed
+}
+
+/// Secrets whose corresponding flag is set
+fn pick(secrets: &[Secret], flags: &[bool]) -> Vec<Secret> {
+    secrets
+        .iter()
+        .zip(flags)
+        .filter(|(_, &selected)| selected)
+        .map(|(secret, _)| secret.clone())
+        .collect()
+}
+
+/// Let the user adjust which variables get stored
+///
+/// Every parsed variable is listed, with the detected ones pre-ticked.
+/// Returns `None` if the user aborts; without a terminal the defaults stand.
+fn select_interactively(secrets: &[Secret], defaults: &[bool]) -> Option<Vec<Secret>> {
+    if !dialoguer::console::Term::stderr().is_term() {
+        return Some(pick(secrets, defaults));
+    }
+
+    let items: Vec<String> = secrets
+        .iter()
+        .map(|s| format!("{} ({})", s.key, s.reason))
+        .collect();
+
+    println!();
+    let chosen = MultiSelect::new()
+        .with_prompt("Select variables to store (space toggles, enter confirms)")
+        .items(&items)
+        .defaults(defaults)
+        .interact_opt()
+        .ok()??;
+
+    let mut flags = vec![false; secrets.len()];
+    for i in chosen {
+        flags[i] = true;
     }
+    Some(pick(secrets, &flags))
+}
+
+/// Parse an expiry given as `YYYY-MM-DD` (midnight UTC) or RFC 3339
+pub fn parse_expiry(input: &str) -> Result<DateTime<Utc>> {
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+
+    DateTime::parse_from_rfc3339(input)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|_| {
+            Error::ValidationError(format!(
+                "Invalid expiry: {}. Use YYYY-MM-DD or RFC 3339",
+                input
+            ))
+        })
 }
@@ -11,7 +11,7 @@ pub fn execute(command: Option<String>, _service_name: &str) -> Result<()> {
     } else {
         let mut buffer = String::new();
         io::stdin().read_to_string(&mut buffer)
-            .map_err(|e| crate::error::Error::IoError(e))?;
+            .map_err(crate::error::Error::IoError)?;
         buffer.trim().to_string()
     };
 
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use crate::error::Result;
+
+pub mod classify;
+pub mod dotenv;
+pub mod pattern;
+pub mod reference;
+pub mod rename;
+pub mod selector;
+
+pub use classify::Classifier;
+pub use dotenv::ParseOptions;
+pub use rename::Renames;
+pub use selector::{Selector, Tags};
+
+/// Whether `key` can be used as an environment variable name
+pub fn is_valid_key(key: &str) -> bool {
+    key.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[derive(Debug, Clone)]
+pub struct Secret {
+    pub key: String,
+    pub value: String,
+    pub sensitive: bool,
+    /// Why the classifier did or didn't consider this sensitive
+    pub reason: String,
+}
+
+pub struct Secrets {
+    pub secrets: Vec<Secret>,
+}
+
+impl Secrets {
+    /// Parse dotenv text and identify sensitive variables
+    pub fn from_env_str(content: &str, options: ParseOptions, classifier: &Classifier) -> Result<Self> {
+        let pairs = dotenv::parse(content, options)?
+            .into_iter()
+            .map(|entry| (entry.key, entry.value));
+
+        Ok(Self::from_pairs(pairs, classifier))
+    }
+
+    /// Classify already-parsed `(key, value)` pairs
+    ///
+    /// Later assignments override earlier ones, as in a shell.
+    pub fn from_pairs<I>(pairs: I, classifier: &Classifier) -> Self
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut secrets: Vec<Secret> = Vec::new();
+        for (key, value) in pairs {
+            let classification = classifier.classify(&key, &value);
+
+            secrets.retain(|s| s.key != key);
+            secrets.push(Secret {
+                key,
+                value,
+                sensitive: classification.sensitive,
+                reason: classification.reason,
+            });
+        }
+
+        Secrets { secrets }
+    }
+
+    /// Keep the secrets `selector` picks; terms that match nothing are logged
+    pub fn filter_by_keys(mut self, selector: &Selector, tags: &Tags) -> Self {
+        let (selected, unmatched) = selector.select(self.secrets, |s| s.key.as_str(), tags);
+        if !unmatched.is_empty() {
+            log::warn!("No variable matches {}", unmatched.join(", "));
+        }
+        self.secrets = selected;
+        self
+    }
+
+    /// Get only sensitive secrets
+    pub fn sensitive_only(&self) -> Vec<Secret> {
+        self.secrets.iter()
+            .filter(|s| s.sensitive)
+            .cloned()
+            .collect()
+    }
+
+    /// Convert to HashMap for easier lookup
+    #[allow(dead_code)]
+    pub fn to_map(&self) -> HashMap<String, String> {
+        self.secrets.iter()
+            .map(|s| (s.key.clone(), s.value.clone()))
+            .collect()
+    }
+}
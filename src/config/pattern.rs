@@ -0,0 +1,62 @@
+/// Case-insensitive glob match supporting `*` (any run) and `?` (one char)
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    glob_match_case_sensitive(&pattern.to_lowercase(), &name.to_lowercase())
+}
+
+/// `glob_match` that tells `aws_*` and `AWS_*` apart, as variable names do
+pub fn glob_match_case_sensitive(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and the name index it was tried against
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("AWS_*", "AWS_SECRET_ACCESS_KEY"));
+        assert!(glob_match("*_URL", "DATABASE_URL"));
+        assert!(glob_match("*PUBLIC*", "NEXT_PUBLIC_API"));
+        assert!(glob_match("KEY_?", "KEY_1"));
+        assert!(glob_match("aws_*", "AWS_REGION"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("AWS_*", "MY_AWS_KEY"));
+        assert!(!glob_match("KEY_?", "KEY_10"));
+        assert!(!glob_match("*_URL", "URL_PREFIX"));
+    }
+
+    #[test]
+    fn test_glob_match_case_sensitive() {
+        assert!(glob_match_case_sensitive("AWS_*", "AWS_REGION"));
+        assert!(!glob_match_case_sensitive("aws_*", "AWS_REGION"));
+        assert!(!glob_match_case_sensitive("KEY_?", "key_1"));
+    }
+}
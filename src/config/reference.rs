@@ -0,0 +1,178 @@
+use std::ops::Range;
+
+use crate::error::{Error, Result};
+
+/// Scheme of references written by `setup --scrub` and resolved by `inject`
+pub const SCHEME: &str = "keychain://";
+
+/// A reference to a stored secret found in a template
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    /// Service named by a `keychain://` URL; `None` for `{{ keychain "KEY" }}`,
+    /// which means the service given on the command line
+    pub service: Option<String>,
+    pub key: String,
+    /// Byte range of the whole reference in the template
+    pub span: Range<usize>,
+}
+
+/// `keychain://<service>/<KEY>`
+pub fn format(service_name: &str, key: &str) -> String {
+    format!("{}{}/{}", SCHEME, service_name, key)
+}
+
+/// Find every reference in `text`
+///
+/// `keychain://` must be followed by `<service>/<KEY>`, otherwise it is an
+/// error. `{{ ... }}` blocks that aren't `{{ keychain "KEY" }}` are left
+/// alone, so templates can still contain other template languages.
+pub fn scan(text: &str) -> Result<Vec<Reference>> {
+    let mut references = Vec::new();
+    let mut pos = 0;
+
+    while pos < text.len() {
+        let rest = &text[pos..];
+        let url = rest.find(SCHEME);
+        let braces = rest.find("{{");
+
+        let start = match (url, braces) {
+            (None, None) => break,
+            (Some(u), Some(b)) => pos + u.min(b),
+            (Some(u), None) => pos + u,
+            (None, Some(b)) => pos + b,
+        };
+
+        if text[start..].starts_with(SCHEME) {
+            let reference = parse_url(text, start)?;
+            pos = reference.span.end;
+            references.push(reference);
+        } else if let Some(reference) = parse_braces(text, start) {
+            pos = reference.span.end;
+            references.push(reference);
+        } else {
+            pos = start + 2;
+        }
+    }
+
+    Ok(references)
+}
+
+/// Replace every reference with the value `lookup` returns for it
+pub fn render<F>(text: &str, references: &[Reference], lookup: F) -> String
+where
+    F: Fn(&Reference) -> String,
+{
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    for reference in references {
+        out.push_str(&text[copied..reference.span.start]);
+        out.push_str(&lookup(reference));
+        copied = reference.span.end;
+    }
+    out.push_str(&text[copied..]);
+    out
+}
+
+fn parse_url(text: &str, start: usize) -> Result<Reference> {
+    let body = &text[start + SCHEME.len()..];
+    let service_len = body
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-')))
+        .unwrap_or(body.len());
+    let key_start = service_len + 1;
+    let key_len = if service_len > 0 && body[service_len..].starts_with('/') {
+        key_length(&body[key_start..])
+    } else {
+        0
+    };
+
+    if key_len == 0 {
+        let line = text[..start].matches('\n').count() + 1;
+        return Err(Error::ValidationError(format!(
+            "line {}: malformed reference; expected {}<service>/<KEY>",
+            line, SCHEME
+        )));
+    }
+
+    Ok(Reference {
+        service: Some(body[..service_len].to_string()),
+        key: body[key_start..key_start + key_len].to_string(),
+        span: start..start + SCHEME.len() + key_start + key_len,
+    })
+}
+
+/// `{{ keychain "KEY" }}` with any amount of inner whitespace
+fn parse_braces(text: &str, start: usize) -> Option<Reference> {
+    let rest = text[start + 2..].trim_start_matches([' ', '\t']);
+    let rest = rest.strip_prefix("keychain")?;
+    let trimmed = rest.trim_start_matches([' ', '\t']);
+    if trimmed.len() == rest.len() {
+        return None;
+    }
+    let rest = trimmed.strip_prefix('"')?;
+    let key_len = key_length(rest);
+    let key = &rest[..key_len];
+    let rest = rest[key_len..].strip_prefix('"')?;
+    let rest = rest.trim_start_matches([' ', '\t']).strip_prefix("}}")?;
+
+    if key_len == 0 {
+        return None;
+    }
+    Some(Reference {
+        service: None,
+        key: key.to_string(),
+        span: start..text.len() - rest.len(),
+    })
+}
+
+/// Length of the variable name at the start of `text`
+fn key_length(text: &str) -> usize {
+    if !text.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        return 0;
+    }
+    text.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finds_both_syntaxes() {
+        let text = "DB=keychain://claude-dev/DB_PASSWORD\ntoken: \"{{ keychain \"API_TOKEN\" }}\"\nx: {{keychain \"A\"}}";
+        let references = scan(text).unwrap();
+
+        assert_eq!(references.len(), 3);
+        assert_eq!(references[0].service.as_deref(), Some("claude-dev"));
+        assert_eq!(references[0].key, "DB_PASSWORD");
+        assert_eq!(&text[references[0].span.clone()], "keychain://claude-dev/DB_PASSWORD");
+        assert_eq!(references[1].service, None);
+        assert_eq!(&text[references[1].span.clone()], "{{ keychain \"API_TOKEN\" }}");
+        assert_eq!(references[2].key, "A");
+    }
+
+    #[test]
+    fn test_leaves_other_templates_alone() {
+        let text = "{{ .Values.x }} {{ keychainX \"A\" }} {{ keychain A }} {{";
+        assert!(scan(text).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_malformed_urls_are_errors() {
+        let err = scan("A=1\nB=keychain://svc\n").unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{}", err);
+        assert!(scan("keychain:///KEY").is_err());
+        assert!(scan("keychain://svc/1KEY").is_err());
+    }
+
+    #[test]
+    fn test_render_substitutes_values() {
+        let text = "url=keychain://s/K?x=1 and {{ keychain \"K\" }}.";
+        let references = scan(text).unwrap();
+        assert_eq!(
+            render(text, &references, |r| format!("<{}>", r.key)),
+            "url=<K>?x=1 and <K>."
+        );
+        assert_eq!(format("svc", "KEY"), "keychain://svc/KEY");
+    }
+}
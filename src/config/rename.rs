@@ -0,0 +1,184 @@
+use log::warn;
+use std::collections::HashMap;
+
+use super::is_valid_key;
+use crate::error::{Error, Result};
+
+/// How stored key names are exposed to a program
+///
+/// Keys named in `map` get exactly the names listed there (one stored key
+/// may appear several times to be exposed under several names). Every other
+/// key loses `strip_prefix` if it has it and then gains `prefix`.
+#[derive(Debug, Clone, Default)]
+pub struct Renames {
+    pub map: Vec<(String, String)>,
+    pub strip_prefix: Option<String>,
+    pub prefix: Option<String>,
+}
+
+impl Renames {
+    /// Add one `OLD=NEW` pair
+    pub fn add(&mut self, pair: &str) -> Result<()> {
+        let (old, new) = split_pair(pair).ok_or_else(|| {
+            Error::ValidationError(format!("Invalid mapping '{}': expected OLD=NEW", pair))
+        })?;
+        self.map.push((old.to_string(), new.to_string()));
+        Ok(())
+    }
+
+    /// Add the `OLD=NEW` lines of a mapping file; blank lines and `#`
+    /// comments are skipped
+    pub fn add_file(&mut self, content: &str) -> Result<()> {
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (old, new) = split_pair(line).ok_or_else(|| {
+                Error::ValidationError(format!("line {}: expected OLD=NEW, got '{}'", i + 1, line))
+            })?;
+            self.map.push((old.to_string(), new.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Rename `secrets`, refusing names that two stored keys would both end
+    /// up with
+    ///
+    /// With `check_names` (output that sets variables), a name produced by a
+    /// rename must also be a valid variable name; stored names are passed
+    /// through as they are.
+    pub fn apply(
+        &self,
+        secrets: Vec<(String, String)>,
+        check_names: bool,
+    ) -> Result<Vec<(String, String)>> {
+        for (old, _) in &self.map {
+            if !secrets.iter().any(|(key, _)| key == old) {
+                warn!("Mapped key {} was not loaded", old);
+            }
+        }
+
+        let mut renamed = Vec::with_capacity(secrets.len());
+        let mut origins: HashMap<String, String> = HashMap::new();
+
+        for (key, value) in secrets {
+            let mut names: Vec<String> = self
+                .map
+                .iter()
+                .filter(|(old, _)| *old == key)
+                .map(|(_, new)| new.clone())
+                .collect();
+            if names.is_empty() {
+                let stripped = match &self.strip_prefix {
+                    Some(prefix) => key.strip_prefix(prefix.as_str()).unwrap_or(&key),
+                    None => &key,
+                };
+                names.push(format!("{}{}", self.prefix.as_deref().unwrap_or(""), stripped));
+            }
+
+            for name in names {
+                if check_names && name != key && !is_valid_key(&name) {
+                    return Err(Error::ValidationError(format!(
+                        "{} would be exposed as '{}', which is not a valid variable name",
+                        key, name
+                    )));
+                }
+                if let Some(other) = origins.insert(name.clone(), key.clone()) {
+                    return Err(Error::ValidationError(format!(
+                        "{} and {} would both be exposed as {}",
+                        other, key, name
+                    )));
+                }
+                renamed.push((name, value.clone()));
+            }
+        }
+
+        Ok(renamed)
+    }
+}
+
+fn split_pair(pair: &str) -> Option<(&str, &str)> {
+    pair.split_once('=')
+        .map(|(old, new)| (old.trim(), new.trim()))
+        .filter(|(old, new)| !old.is_empty() && !new.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secrets() -> Vec<(String, String)> {
+        [
+            ("ANTHROPIC_AUTH_TOKEN", "sk-ant"),
+            ("APP_DB_PASSWORD", "pw"),
+            ("GITHUB_TOKEN", "ghp"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+    }
+
+    fn names(renames: &Renames) -> Result<Vec<String>> {
+        Ok(renames.apply(secrets(), true)?.into_iter().map(|(k, _)| k).collect())
+    }
+
+    #[test]
+    fn test_maps_win_over_prefixes() {
+        let mut renames = Renames {
+            strip_prefix: Some("APP_".to_string()),
+            prefix: Some("MY_".to_string()),
+            ..Renames::default()
+        };
+        renames.add("ANTHROPIC_AUTH_TOKEN=ANTHROPIC_API_KEY").unwrap();
+
+        assert_eq!(
+            names(&renames).unwrap(),
+            ["ANTHROPIC_API_KEY", "MY_DB_PASSWORD", "MY_GITHUB_TOKEN"]
+        );
+    }
+
+    #[test]
+    fn test_one_key_can_have_several_names() {
+        let mut renames = Renames::default();
+        let file = "# tool A wants the API name\n\
+                    ANTHROPIC_AUTH_TOKEN=ANTHROPIC_API_KEY\n\n\
+                    ANTHROPIC_AUTH_TOKEN = ANTHROPIC_AUTH_TOKEN\n";
+        renames.add_file(file).unwrap();
+
+        let renamed = renames.apply(secrets(), true).unwrap();
+        assert_eq!(renamed[0], ("ANTHROPIC_API_KEY".to_string(), "sk-ant".to_string()));
+        assert_eq!(renamed[1], ("ANTHROPIC_AUTH_TOKEN".to_string(), "sk-ant".to_string()));
+        assert_eq!(renamed.len(), 4);
+    }
+
+    #[test]
+    fn test_stored_names_are_not_checked() {
+        let stored = vec![("my-key".to_string(), "v".to_string())];
+        let renamed = Renames::default().apply(stored.clone(), true).unwrap();
+        assert_eq!(renamed, stored);
+
+        let mut renames = Renames::default();
+        renames.add("my-key=MY_KEY").unwrap();
+        assert_eq!(renames.apply(stored, true).unwrap()[0].0, "MY_KEY");
+    }
+
+    #[test]
+    fn test_rejects_collisions_and_bad_names() {
+        let mut renames = Renames::default();
+        renames.add("GITHUB_TOKEN=APP_DB_PASSWORD").unwrap();
+        let err = names(&renames).unwrap_err();
+        assert!(err.to_string().contains("would both be exposed as APP_DB_PASSWORD"), "{}", err);
+
+        let renames = Renames {
+            prefix: Some("1".to_string()),
+            ..Renames::default()
+        };
+        assert!(names(&renames).is_err());
+        assert!(renames.apply(secrets(), false).is_ok());
+
+        assert!(Renames::default().add("NO_EQUALS").is_err());
+        let err = Renames::default().add_file("A=B\n=C\n").unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{}", err);
+    }
+}
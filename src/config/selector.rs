@@ -0,0 +1,250 @@
+use regex::Regex;
+use std::collections::HashMap;
+
+use super::pattern::glob_match_case_sensitive;
+use crate::error::{Error, Result};
+
+/// Index tags per key, for `tag:` terms
+pub type Tags = HashMap<String, Vec<String>>;
+
+/// A `--keys` list: which keys to pick
+///
+/// Comma-separated terms, each one of
+/// - `NAME`: exactly that key
+/// - `AWS_*`, `KEY_?`: a glob; like every term, case-sensitive
+/// - `/^AWS_(ID|SECRET)$/`: a regex, matched anywhere unless anchored; may
+///   contain commas
+/// - `tag:db`: every key tagged `db` in the index
+///
+/// and any of them prefixed with `!` to exclude what it matches. With only
+/// exclusions, everything else is selected.
+#[derive(Debug)]
+pub struct Selector {
+    include: Vec<Term>,
+    exclude: Vec<Term>,
+}
+
+#[derive(Debug)]
+struct Term {
+    /// As written, for error messages
+    text: String,
+    kind: Kind,
+}
+
+#[derive(Debug)]
+enum Kind {
+    Exact(String),
+    Glob(String),
+    Regex(Regex),
+    Tag(String),
+}
+
+impl Selector {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut selector = Selector {
+            include: Vec::new(),
+            exclude: Vec::new(),
+        };
+
+        for text in split_terms(spec) {
+            let (negated, body) = match text.strip_prefix('!') {
+                Some(body) => (true, body.trim_start()),
+                None => (false, text.as_str()),
+            };
+            let term = Term {
+                kind: Kind::parse(body)?,
+                text: text.clone(),
+            };
+            if negated {
+                selector.exclude.push(term);
+            } else {
+                selector.include.push(term);
+            }
+        }
+
+        if selector.include.is_empty() && selector.exclude.is_empty() {
+            return Err(Error::ValidationError("Empty key selector".to_string()));
+        }
+        Ok(selector)
+    }
+
+    /// Whether any term needs index tags
+    pub fn uses_tags(&self) -> bool {
+        self.include
+            .iter()
+            .chain(&self.exclude)
+            .any(|term| matches!(term.kind, Kind::Tag(_)))
+    }
+
+    /// Keep the items whose key is selected, in order
+    ///
+    /// Also returns the include terms that matched no item at all (before
+    /// exclusions), so callers can report typos.
+    pub fn select<T, F>(&self, items: Vec<T>, key: F, tags: &Tags) -> (Vec<T>, Vec<String>)
+    where
+        F: Fn(&T) -> &str,
+    {
+        let mut used = vec![false; self.include.len()];
+        let mut selected = Vec::new();
+
+        for item in items {
+            let name = key(&item);
+            let mut included = self.include.is_empty();
+            for (i, term) in self.include.iter().enumerate() {
+                if term.kind.matches(name, tags) {
+                    used[i] = true;
+                    included = true;
+                }
+            }
+            if included && !self.exclude.iter().any(|term| term.kind.matches(name, tags)) {
+                selected.push(item);
+            }
+        }
+
+        let unmatched = self
+            .include
+            .iter()
+            .zip(used)
+            .filter(|(_, used)| !used)
+            .map(|(term, _)| term.text.clone())
+            .collect();
+        (selected, unmatched)
+    }
+}
+
+impl Kind {
+    fn parse(body: &str) -> Result<Self> {
+        if let Some(tag) = body.strip_prefix("tag:") {
+            if tag.is_empty() {
+                return Err(Error::ValidationError("Empty tag in key selector".to_string()));
+            }
+            return Ok(Kind::Tag(tag.to_string()));
+        }
+
+        if body.len() >= 2 && body.starts_with('/') && body.ends_with('/') {
+            let pattern = &body[1..body.len() - 1];
+            return Regex::new(pattern).map(Kind::Regex).map_err(|e| {
+                Error::ValidationError(format!("Invalid regex /{}/ in key selector: {}", pattern, e))
+            });
+        }
+
+        if body.contains(['*', '?']) {
+            Ok(Kind::Glob(body.to_string()))
+        } else {
+            Ok(Kind::Exact(body.to_string()))
+        }
+    }
+
+    fn matches(&self, key: &str, tags: &Tags) -> bool {
+        match self {
+            Kind::Exact(name) => name == key,
+            Kind::Glob(pattern) => glob_match_case_sensitive(pattern, key),
+            Kind::Regex(regex) => regex.is_match(key),
+            Kind::Tag(tag) => tags.get(key).is_some_and(|t| t.contains(tag)),
+        }
+    }
+}
+
+/// Split on commas, except inside a `/regex/` term
+fn split_terms(spec: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut current = String::new();
+    let mut in_regex = false;
+    let mut escaped = false;
+
+    for c in spec.chars() {
+        if in_regex {
+            current.push(c);
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '/' => in_regex = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            ',' => terms.push(std::mem::take(&mut current)),
+            '/' if matches!(current.trim_start(), "" | "!") => {
+                in_regex = true;
+                current.push(c);
+            }
+            _ => current.push(c),
+        }
+    }
+    terms.push(current);
+
+    terms
+        .into_iter()
+        .map(|term| term.trim().to_string())
+        .filter(|term| !term.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEYS: [&str; 6] = [
+        "AWS_ACCESS_KEY_ID",
+        "AWS_SECRET_ACCESS_KEY",
+        "DB_PASSWORD",
+        "DEBUG_TOKEN",
+        "GITHUB_TOKEN",
+        "REDIS_URL",
+    ];
+
+    fn pick(spec: &str, tags: &Tags) -> (Vec<&'static str>, Vec<String>) {
+        Selector::parse(spec).unwrap().select(KEYS.to_vec(), |k| k, tags)
+    }
+
+    #[test]
+    fn test_exact_glob_and_regex_terms() {
+        let tags = Tags::new();
+        assert_eq!(pick("DB_PASSWORD, REDIS_URL", &tags).0, ["DB_PASSWORD", "REDIS_URL"]);
+        assert_eq!(pick("AWS_*", &tags).0, ["AWS_ACCESS_KEY_ID", "AWS_SECRET_ACCESS_KEY"]);
+        assert_eq!(
+            pick("/^(DB|REDIS)_/,GITHUB_TOKEN", &tags).0,
+            ["DB_PASSWORD", "GITHUB_TOKEN", "REDIS_URL"]
+        );
+        assert_eq!(pick("/_(ID|URL){1,2}$/", &tags).0, ["AWS_ACCESS_KEY_ID", "REDIS_URL"]);
+    }
+
+    #[test]
+    fn test_terms_are_case_sensitive() {
+        let tags = Tags::new();
+        assert!(pick("aws_*,db_password,/redis/", &tags).0.is_empty());
+        assert_eq!(pick("*_TOKEN,!debug_*", &tags).0, ["DEBUG_TOKEN", "GITHUB_TOKEN"]);
+    }
+
+    #[test]
+    fn test_exclusions_and_tags() {
+        let tags: Tags = [
+            ("DB_PASSWORD".to_string(), vec!["db".to_string()]),
+            ("REDIS_URL".to_string(), vec!["db".to_string(), "cache".to_string()]),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(pick("*_TOKEN,!DEBUG_*", &tags).0, ["GITHUB_TOKEN"]);
+        assert_eq!(pick("!AWS_*, !tag:db", &tags).0, ["DEBUG_TOKEN", "GITHUB_TOKEN"]);
+        assert_eq!(pick("tag:db,!tag:cache", &tags).0, ["DB_PASSWORD"]);
+        assert!(Selector::parse("tag:db").unwrap().uses_tags());
+        assert!(!Selector::parse("DB_*").unwrap().uses_tags());
+    }
+
+    #[test]
+    fn test_reports_terms_that_match_nothing() {
+        let (selected, unmatched) =
+            pick("DB_PASWORD,GITHUB_TOKEN,GCP_*,tag:none,!NOPE", &Tags::new());
+        assert_eq!(selected, ["GITHUB_TOKEN"]);
+        assert_eq!(unmatched, ["DB_PASWORD", "GCP_*", "tag:none"]);
+    }
+
+    #[test]
+    fn test_rejects_bad_selectors() {
+        assert!(Selector::parse("/[/").is_err());
+        assert!(Selector::parse(" , ").is_err());
+        assert!(Selector::parse("tag:").is_err());
+    }
+}
@@ -4,14 +4,14 @@ use std::io;
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum Error {
     IoError(io::Error),
     KeychainError(String),
-    #[allow(dead_code)]
     EnvParseError(String),
     ValidationError(String),
-    #[allow(dead_code)]
     ConfigError(String),
+    ImportError(String),
     JsonError(serde_json::error::Error),
 }
 
@@ -23,6 +23,7 @@ impl fmt::Display for Error {
             Error::EnvParseError(msg) => write!(f, "Environment parse error: {}", msg),
             Error::ValidationError(msg) => write!(f, "Validation error: {}", msg),
             Error::ConfigError(msg) => write!(f, "Config error: {}", msg),
+            Error::ImportError(msg) => write!(f, "Import error: {}", msg),
             Error::JsonError(e) => write!(f, "JSON error: {}", e),
         }
     }
@@ -0,0 +1,149 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use super::items::{Field, Item};
+use crate::error::{Error, Result};
+
+#[derive(Deserialize)]
+struct Export {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    folders: Vec<Folder>,
+    #[serde(default)]
+    items: Vec<ExportItem>,
+}
+
+#[derive(Deserialize)]
+struct Folder {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportItem {
+    name: String,
+    folder_id: Option<String>,
+    notes: Option<String>,
+    login: Option<Login>,
+    #[serde(default)]
+    fields: Vec<CustomField>,
+}
+
+#[derive(Deserialize)]
+struct Login {
+    username: Option<String>,
+    password: Option<String>,
+    totp: Option<String>,
+    uris: Option<Vec<Uri>>,
+}
+
+#[derive(Deserialize)]
+struct Uri {
+    uri: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CustomField {
+    name: Option<String>,
+    value: Option<String>,
+}
+
+/// Parse an unencrypted Bitwarden JSON export
+///
+/// Login credentials become built-in fields and the item's custom fields
+/// keep their names. Linked fields carry no value and are dropped.
+pub fn parse(content: &str) -> Result<Vec<Item>> {
+    let export: Export = serde_json::from_str(content)
+        .map_err(|e| Error::ImportError(format!("Invalid Bitwarden export: {}", e)))?;
+    if export.encrypted {
+        return Err(Error::ImportError(
+            "Encrypted Bitwarden exports are not supported; export as unencrypted JSON".to_string(),
+        ));
+    }
+
+    let folders: HashMap<&str, &str> = export
+        .folders
+        .iter()
+        .map(|f| (f.id.as_str(), f.name.as_str()))
+        .collect();
+
+    Ok(export
+        .items
+        .iter()
+        .map(|entry| {
+            let mut fields = Vec::new();
+            if let Some(login) = &entry.login {
+                let builtins = [
+                    ("username", &login.username),
+                    ("password", &login.password),
+                    ("totp", &login.totp),
+                ];
+                for (name, value) in builtins {
+                    if let Some(value) = value {
+                        fields.push(Field::builtin(name, value));
+                    }
+                }
+                let uri = login.uris.iter().flatten().find_map(|u| u.uri.as_deref());
+                if let Some(uri) = uri {
+                    fields.push(Field::builtin("url", uri));
+                }
+            }
+            if let Some(notes) = &entry.notes {
+                fields.push(Field::builtin("notes", notes));
+            }
+            for custom in &entry.fields {
+                if let (Some(name), Some(value)) = (&custom.name, &custom.value) {
+                    fields.push(Field::custom(name, value));
+                }
+            }
+
+            Item {
+                folder: entry
+                    .folder_id
+                    .as_deref()
+                    .and_then(|id| folders.get(id))
+                    .unwrap_or(&"")
+                    .to_string(),
+                title: entry.name.clone(),
+                fields,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::fixture_path;
+    use std::fs;
+
+    #[test]
+    fn test_parses_logins_notes_and_custom_fields() {
+        let content = fs::read_to_string(fixture_path("bitwarden.json")).unwrap();
+        let items = parse(&content).unwrap();
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].folder, "Work");
+        assert_eq!(items[0].title, "Stripe");
+        assert_eq!(
+            items[0].fields,
+            vec![
+                Field::builtin("username", "ops@example.com"),
+                Field::builtin("password", "stripe-login-pw"),
+                Field::builtin("url", "https://dashboard.stripe.com"),
+                Field::builtin("notes", "live account"),
+                Field::custom("secret key", "sk_live_bw"),
+            ]
+        );
+        assert_eq!(items[1].folder, "");
+        assert_eq!(items[1].fields[1], Field::custom("OPENAI_API_KEY", "sk-bw-openai"));
+    }
+
+    #[test]
+    fn test_rejects_encrypted_exports() {
+        let err = parse(r#"{"encrypted": true, "passwordProtected": true, "data": "..."}"#).unwrap_err();
+        assert!(err.to_string().contains("unencrypted"), "{}", err);
+    }
+}
@@ -0,0 +1,152 @@
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::dotenv;
+use crate::config::ParseOptions;
+use crate::error::{Error, Result};
+
+/// One `env_file` reference from a compose service
+#[derive(Debug, PartialEq)]
+struct EnvFile {
+    path: PathBuf,
+    required: bool,
+}
+
+/// Read every dotenv file referenced by `env_file` in a compose file
+///
+/// Services are visited in name order and each file is read once, in the
+/// order first listed; later assignments override earlier ones, as compose
+/// does. Paths are relative to the compose file. Inline `environment:`
+/// entries are not imported: they usually hold `${VAR}` references rather
+/// than values.
+pub fn parse(content: &str, base_dir: &Path, options: ParseOptions) -> Result<Vec<(String, String)>> {
+    let document: Value = serde_yaml::from_str(content)
+        .map_err(|e| Error::ImportError(format!("Invalid compose file: {}", e)))?;
+
+    let env_files = env_files(&document, base_dir)?;
+    if env_files.is_empty() {
+        return Err(Error::ImportError("No env_file entries in compose file".to_string()));
+    }
+
+    let mut pairs = Vec::new();
+    for env_file in env_files {
+        let content = match fs::read_to_string(&env_file.path) {
+            Ok(content) => content,
+            Err(_) if !env_file.required => continue,
+            Err(e) => {
+                return Err(Error::ImportError(format!(
+                    "Failed to read {}: {}",
+                    env_file.path.display(),
+                    e
+                )))
+            }
+        };
+        let entries = dotenv::parse(&content, options).map_err(|e| {
+            Error::ImportError(format!("{}: {}", env_file.path.display(), e))
+        })?;
+        pairs.extend(entries.into_iter().map(|entry| (entry.key, entry.value)));
+    }
+
+    Ok(pairs)
+}
+
+/// `env_file` may be a string, a list of strings, or a list of
+/// `{path, required}` objects
+fn env_files(document: &Value, base_dir: &Path) -> Result<Vec<EnvFile>> {
+    let services = document
+        .get("services")
+        .and_then(Value::as_object)
+        .ok_or_else(|| Error::ImportError("Compose file has no services".to_string()))?;
+
+    let mut files: Vec<EnvFile> = Vec::new();
+    for (service, definition) in services {
+        let references = match definition.get("env_file") {
+            None => continue,
+            Some(Value::Array(items)) => items.iter().collect(),
+            Some(single) => vec![single],
+        };
+
+        for reference in references {
+            let (path, required) = match reference {
+                Value::String(path) => (path.as_str(), true),
+                Value::Object(fields) => match fields.get("path").and_then(Value::as_str) {
+                    Some(path) => (
+                        path,
+                        fields.get("required").and_then(Value::as_bool).unwrap_or(true),
+                    ),
+                    None => {
+                        return Err(Error::ImportError(format!(
+                            "Service {}: env_file entry without a path",
+                            service
+                        )))
+                    }
+                },
+                _ => {
+                    return Err(Error::ImportError(format!(
+                        "Service {}: unsupported env_file entry",
+                        service
+                    )))
+                }
+            };
+
+            let path = base_dir.join(path);
+            if !files.iter().any(|f| f.path == path) {
+                files.push(EnvFile { path, required });
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn pair(key: &str, value: &str) -> (String, String) {
+        (key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn test_reads_env_files_of_every_service() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("common.env"), "DB_PASSWORD=one\nLOG_LEVEL=info\n").unwrap();
+        fs::write(dir.path().join("api.env"), "DB_PASSWORD='two'\nAPI_TOKEN=tok\n").unwrap();
+
+        let compose = "\
+services:
+  api:
+    image: api
+    env_file:
+      - common.env
+      - path: api.env
+      - path: missing.env
+        required: false
+  worker:
+    env_file: common.env
+  db:
+    image: postgres
+";
+        assert_eq!(
+            parse(compose, dir.path(), ParseOptions::default()).unwrap(),
+            vec![
+                pair("DB_PASSWORD", "one"),
+                pair("LOG_LEVEL", "info"),
+                pair("DB_PASSWORD", "two"),
+                pair("API_TOKEN", "tok"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_required_file_is_an_error() {
+        let dir = TempDir::new().unwrap();
+        let compose = "services:\n  api:\n    env_file: nope.env\n";
+        let err = parse(compose, dir.path(), ParseOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("nope.env"), "{}", err);
+
+        assert!(parse("services:\n  api:\n    image: x\n", dir.path(), ParseOptions::default()).is_err());
+    }
+}
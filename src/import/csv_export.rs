@@ -0,0 +1,119 @@
+use super::items::{Field, Item};
+use crate::error::{Error, Result};
+
+/// What a CSV column holds, recognised by header name
+#[derive(Clone, Copy, PartialEq)]
+enum Column {
+    Title,
+    /// KeePass group path; its first segment is the database root
+    Group,
+    Vault,
+    Builtin(&'static str),
+    Ignored,
+    Custom,
+}
+
+fn column(header: &str) -> Column {
+    match header.trim().to_ascii_lowercase().as_str() {
+        "title" | "name" | "account" => Column::Title,
+        "group" => Column::Group,
+        "vault" | "folder" => Column::Vault,
+        "password" | "login_password" => Column::Builtin("password"),
+        "username" | "login name" | "login_username" => Column::Builtin("username"),
+        "url" | "website" | "web site" | "login_uri" => Column::Builtin("url"),
+        "notes" | "comments" => Column::Builtin("notes"),
+        "otpauth" | "totp" => Column::Builtin("totp"),
+        "favorite" | "archived" | "tags" | "type" | "icon" | "last modified" | "created" => {
+            Column::Ignored
+        }
+        _ => Column::Custom,
+    }
+}
+
+/// Parse a 1Password or KeePass(XC) CSV export
+///
+/// Both write one row per entry under a header row; columns are matched by
+/// name, so either tool's layout (and KeePass 2's `Account`/`Login Name`
+/// variant) works. Columns that aren't recognised become custom fields.
+pub fn parse(content: &str) -> Result<Vec<Item>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(content.as_bytes());
+
+    let headers: Vec<(String, Column)> = reader
+        .headers()
+        .map_err(|e| Error::ImportError(format!("Invalid CSV export: {}", e)))?
+        .iter()
+        .map(|h| (h.trim().to_string(), column(h)))
+        .collect();
+    if !headers.iter().any(|(_, c)| *c == Column::Title)
+        || !headers.iter().any(|(_, c)| *c == Column::Builtin("password"))
+    {
+        return Err(Error::ImportError(
+            "CSV export needs a header row with title and password columns".to_string(),
+        ));
+    }
+
+    let mut items = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| Error::ImportError(format!("Invalid CSV export: {}", e)))?;
+
+        let mut item = Item::default();
+        for ((header, column), value) in headers.iter().zip(record.iter()) {
+            match column {
+                Column::Title => item.title = value.to_string(),
+                Column::Group => {
+                    item.folder = value.split_once('/').map(|(_, rest)| rest).unwrap_or("").to_string()
+                }
+                Column::Vault => item.folder = value.to_string(),
+                Column::Builtin(name) => item.fields.push(Field::builtin(name, value)),
+                Column::Ignored => {}
+                Column::Custom => item.fields.push(Field::custom(header, value)),
+            }
+        }
+        items.push(item);
+    }
+
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::fixture_path;
+    use std::fs;
+
+    #[test]
+    fn test_parses_1password_csv() {
+        let content = fs::read_to_string(fixture_path("1password.csv")).unwrap();
+        let items = parse(&content).unwrap();
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].title, "GitHub");
+        assert!(items[0].fields.contains(&Field::builtin("password", "gh-login-pw")));
+        assert!(items[0].fields.contains(&Field::builtin("notes", "uses \"quotes\"")));
+        assert_eq!(items[1].title, "Anthropic API");
+        assert!(items[1].fields.contains(&Field::builtin("notes", "line one\nline two")));
+        assert!(items[1].fields.iter().all(|f| !f.custom));
+    }
+
+    #[test]
+    fn test_parses_keepassxc_csv() {
+        let content = fs::read_to_string(fixture_path("keepassxc.csv")).unwrap();
+        let items = parse(&content).unwrap();
+
+        assert_eq!(items[0].folder, "Cloud");
+        assert_eq!(items[0].title, "AWS");
+        assert!(items[0].fields.contains(&Field::builtin("password", "aws-secret-kp")));
+        assert_eq!(items[1].folder, "");
+    }
+
+    #[test]
+    fn test_keepass2_headers_and_missing_columns() {
+        let items = parse("\"Account\",\"Login Name\",\"Password\",\"Web Site\",\"Comments\"\n\"Jira\",\"me\",\"pw\",\"\",\"\"\n").unwrap();
+        assert_eq!(items[0].title, "Jira");
+        assert!(items[0].fields.contains(&Field::builtin("username", "me")));
+
+        assert!(parse("name,value\na,b\n").is_err());
+    }
+}
@@ -0,0 +1,251 @@
+use super::{reject_collisions, KeyCase};
+use crate::config::pattern::glob_match;
+use crate::error::{Error, Result};
+
+/// One password manager entry, reduced to what the importers share
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Item {
+    /// Folder, vault or group path (`work/payments`), empty at the top level
+    pub folder: String,
+    pub title: String,
+    pub fields: Vec<Field>,
+}
+
+/// A named value of an item
+///
+/// Built-in fields use the names `password`, `username`, `url`, `notes` and
+/// `totp`; anything else the user added is `custom`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub value: String,
+    pub custom: bool,
+}
+
+impl Field {
+    pub fn builtin(name: &str, value: &str) -> Self {
+        Field {
+            name: name.to_string(),
+            value: value.to_string(),
+            custom: false,
+        }
+    }
+
+    pub fn custom(name: &str, value: &str) -> Self {
+        Field {
+            name: name.to_string(),
+            value: value.to_string(),
+            custom: true,
+        }
+    }
+}
+
+/// Which fields of an item are imported and what they are called
+///
+/// `template` may use `{folder}`, `{title}` and `{field}`; each placeholder
+/// is reduced to `[A-Za-z0-9_]` with runs of other characters collapsed to
+/// one `_`. An empty placeholder takes the literal text after it (or, at the
+/// end, before it) along, so `{folder}_{title}` is just `TITLE` at the top
+/// level. `fields` holds name patterns (`*`, `?`); without it the password
+/// and every custom field are taken.
+#[derive(Debug, Clone)]
+pub struct ItemRule {
+    pub template: String,
+    pub fields: Option<Vec<String>>,
+}
+
+impl Default for ItemRule {
+    fn default() -> Self {
+        ItemRule {
+            template: "{title}_{field}".to_string(),
+            fields: None,
+        }
+    }
+}
+
+enum Token<'a> {
+    Literal(&'a str),
+    Folder,
+    Title,
+    Field,
+}
+
+impl ItemRule {
+    /// Map every selected, non-empty field to a `(key, value)` pair
+    pub fn pairs(&self, items: &[Item], case: KeyCase) -> Result<Vec<(String, String)>> {
+        let tokens = self.tokens()?;
+
+        let mut origins = Vec::new();
+        let mut values = Vec::new();
+        for item in items {
+            for field in item.fields.iter().filter(|f| self.selects(f)) {
+                let key = case.apply(render(&tokens, item, field));
+                let origin = if item.folder.is_empty() {
+                    format!("\"{}\" {}", item.title, field.name)
+                } else {
+                    format!("\"{}/{}\" {}", item.folder, item.title, field.name)
+                };
+                origins.push((key.clone(), origin));
+                values.push((key, field.value.clone()));
+            }
+        }
+        reject_collisions(&origins)?;
+
+        Ok(values)
+    }
+
+    fn selects(&self, field: &Field) -> bool {
+        if field.value.is_empty() {
+            return false;
+        }
+        match &self.fields {
+            None => field.custom || field.name == "password",
+            Some(patterns) => patterns.iter().any(|p| glob_match(p, &field.name)),
+        }
+    }
+
+    fn tokens(&self) -> Result<Vec<Token<'_>>> {
+        let mut tokens = Vec::new();
+        let mut rest = self.template.as_str();
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                tokens.push(Token::Literal(&rest[..start]));
+            }
+            let end = rest[start..].find('}').ok_or_else(|| {
+                Error::ImportError(format!("Unclosed placeholder in template {:?}", self.template))
+            })? + start;
+            tokens.push(match &rest[start + 1..end] {
+                "folder" => Token::Folder,
+                "title" => Token::Title,
+                "field" => Token::Field,
+                other => {
+                    return Err(Error::ImportError(format!(
+                        "Unknown placeholder {{{}}} in template; use {{folder}}, {{title}} or {{field}}",
+                        other
+                    )))
+                }
+            });
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            tokens.push(Token::Literal(rest));
+        }
+        Ok(tokens)
+    }
+}
+
+fn render(tokens: &[Token], item: &Item, field: &Field) -> String {
+    let parts: Vec<(bool, String)> = tokens
+        .iter()
+        .map(|token| match token {
+            Token::Literal(text) => (false, text.to_string()),
+            Token::Folder => (true, segment(&item.folder)),
+            Token::Title => (true, segment(&item.title)),
+            Token::Field => (true, segment(&field.name)),
+        })
+        .collect();
+
+    let mut keep = vec![true; parts.len()];
+    for (i, (placeholder, text)) in parts.iter().enumerate() {
+        if *placeholder && text.is_empty() {
+            if i + 1 < parts.len() && !parts[i + 1].0 {
+                keep[i + 1] = false;
+            } else if i > 0 && !parts[i - 1].0 {
+                keep[i - 1] = false;
+            }
+        }
+    }
+
+    parts
+        .into_iter()
+        .zip(keep)
+        .filter(|(_, keep)| *keep)
+        .map(|((_, text), _)| text)
+        .collect()
+}
+
+/// `My API-key (prod)` -> `My_API_key_prod`
+fn segment(text: &str) -> String {
+    let mut out = String::new();
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            out.push(c);
+        } else if !out.is_empty() && !out.ends_with('_') {
+            out.push('_');
+        }
+    }
+    out.trim_end_matches('_').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(folder: &str, title: &str, fields: Vec<Field>) -> Item {
+        Item {
+            folder: folder.to_string(),
+            title: title.to_string(),
+            fields,
+        }
+    }
+
+    fn keys(rule: &ItemRule, items: &[Item]) -> Vec<String> {
+        rule.pairs(items, KeyCase::Upper)
+            .unwrap()
+            .into_iter()
+            .map(|(k, _)| k)
+            .collect()
+    }
+
+    #[test]
+    fn test_default_rule_takes_password_and_custom_fields() {
+        let items = vec![item(
+            "",
+            "Stripe (live)",
+            vec![
+                Field::builtin("username", "ops@example.com"),
+                Field::builtin("password", "pw"),
+                Field::custom("api key", "sk_live_1"),
+                Field::custom("empty", ""),
+            ],
+        )];
+
+        assert_eq!(
+            keys(&ItemRule::default(), &items),
+            vec!["STRIPE_LIVE_PASSWORD", "STRIPE_LIVE_API_KEY"]
+        );
+    }
+
+    #[test]
+    fn test_template_and_field_patterns() {
+        let items = vec![
+            item("work/payments", "OPENAI_API_KEY", vec![Field::builtin("password", "sk-1")]),
+            item("", "GITHUB_TOKEN", vec![Field::builtin("password", "ghp_1")]),
+        ];
+        let rule = ItemRule {
+            template: "{folder}__{title}".to_string(),
+            fields: Some(vec!["pass*".to_string()]),
+        };
+
+        assert_eq!(
+            keys(&rule, &items),
+            vec!["WORK_PAYMENTS__OPENAI_API_KEY", "GITHUB_TOKEN"]
+        );
+    }
+
+    #[test]
+    fn test_rejects_bad_templates_and_collisions() {
+        let items = vec![
+            item("", "db", vec![Field::builtin("password", "a")]),
+            item("", "DB", vec![Field::builtin("password", "b")]),
+        ];
+        let err = ItemRule::default().pairs(&items, KeyCase::Upper).unwrap_err();
+        assert!(err.to_string().contains("DB_PASSWORD"), "{}", err);
+
+        let rule = ItemRule {
+            template: "{name}".to_string(),
+            fields: None,
+        };
+        assert!(rule.pairs(&items, KeyCase::Upper).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use log::debug;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{reject_collisions, KeyRule};
+use crate::error::{Error, Result};
+
+/// Collect the entries of every `Secret` in a (multi-document) manifest
+///
+/// `data` values are base64-decoded; `stringData` is taken as is and wins
+/// over `data` for the same key, as the API server does. Documents of other
+/// kinds are skipped and `List` wrappers are unpacked.
+pub fn parse(content: &str, rule: &KeyRule) -> Result<Vec<(String, String)>> {
+    let mut entries = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(content) {
+        let value = Value::deserialize(document)
+            .map_err(|e| Error::ImportError(format!("Invalid manifest: {}", e)))?;
+        collect(&value, &mut entries)?;
+    }
+
+    if entries.is_empty() {
+        return Err(Error::ImportError("No Secret found in manifest".to_string()));
+    }
+
+    let origins: Vec<(String, String)> = entries
+        .iter()
+        .map(|(origin, key, _)| (rule.key(&[key]), origin.clone()))
+        .collect();
+    reject_collisions(&origins)?;
+
+    Ok(origins
+        .into_iter()
+        .zip(entries)
+        .map(|((key, _), (_, _, value))| (key, value))
+        .collect())
+}
+
+/// Push `(origin, key, value)` for each entry of the Secret(s) in `value`
+fn collect(value: &Value, entries: &mut Vec<(String, String, String)>) -> Result<()> {
+    match value.get("kind").and_then(Value::as_str) {
+        Some("List") => {
+            for item in value.get("items").and_then(Value::as_array).into_iter().flatten() {
+                collect(item, entries)?;
+            }
+            return Ok(());
+        }
+        Some("Secret") => {}
+        kind => {
+            debug!("Skipping manifest of kind {:?}", kind);
+            return Ok(());
+        }
+    }
+
+    let name = value
+        .pointer("/metadata/name")
+        .and_then(Value::as_str)
+        .unwrap_or("<unnamed>");
+
+    let mut decoded: Vec<(String, String)> = Vec::new();
+    for (key, encoded) in fields(value, "data") {
+        let bytes = encoded
+            .as_str()
+            .and_then(|s| STANDARD.decode(s.trim()).ok())
+            .ok_or_else(|| Error::ImportError(format!("Secret {}: data.{} is not valid base64", name, key)))?;
+        let text = String::from_utf8(bytes)
+            .map_err(|_| Error::ImportError(format!("Secret {}: data.{} is not valid UTF-8", name, key)))?;
+        decoded.push((key.clone(), text));
+    }
+    for (key, plain) in fields(value, "stringData") {
+        let text = match plain {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        match decoded.iter_mut().find(|(k, _)| k == key) {
+            Some(entry) => entry.1 = text,
+            None => decoded.push((key.clone(), text)),
+        }
+    }
+
+    entries.extend(
+        decoded
+            .into_iter()
+            .map(|(key, text)| (format!("Secret {}: {}", name, key), key, text)),
+    );
+    Ok(())
+}
+
+fn fields<'a>(value: &'a Value, section: &str) -> impl Iterator<Item = (&'a String, &'a Value)> {
+    value
+        .get(section)
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(key: &str, value: &str) -> (String, String) {
+        (key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn test_decodes_data_and_prefers_string_data() {
+        let manifest = "\
+apiVersion: v1
+kind: Secret
+metadata:
+  name: db
+data:
+  password: aHVudGVyMg==
+  username: YWRtaW4=
+stringData:
+  username: root
+  tls.key: |
+    -----BEGIN KEY-----
+";
+        assert_eq!(
+            parse(manifest, &KeyRule::default()).unwrap(),
+            vec![
+                pair("PASSWORD", "hunter2"),
+                pair("USERNAME", "root"),
+                pair("TLS_KEY", "-----BEGIN KEY-----\n"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reads_every_secret_and_skips_other_kinds() {
+        let manifest = "\
+apiVersion: v1
+kind: ConfigMap
+data:
+  LOG_LEVEL: debug
+---
+apiVersion: v1
+kind: List
+items:
+  - kind: Secret
+    metadata: {name: api}
+    stringData: {API_TOKEN: tok}
+---
+kind: Secret
+metadata: {name: db}
+data: {DB_PASSWORD: aHVudGVyMg==}
+";
+        assert_eq!(
+            parse(manifest, &KeyRule::default()).unwrap(),
+            vec![pair("API_TOKEN", "tok"), pair("DB_PASSWORD", "hunter2")]
+        );
+    }
+
+    #[test]
+    fn test_rejects_bad_data_and_duplicates() {
+        let bad = "kind: Secret\nmetadata: {name: db}\ndata: {password: '***'}\n";
+        let err = parse(bad, &KeyRule::default()).unwrap_err();
+        assert!(err.to_string().contains("data.password"), "{}", err);
+
+        let twice = "kind: Secret\nstringData: {token: a}\n---\nkind: Secret\nstringData: {TOKEN: b}\n";
+        assert!(parse(twice, &KeyRule::default()).is_err());
+
+        assert!(parse("kind: ConfigMap\n", &KeyRule::default()).is_err());
+    }
+}
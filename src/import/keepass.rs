@@ -0,0 +1,151 @@
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+use super::items::{Field, Item};
+use crate::error::{Error, Result};
+
+/// Parse a KeePass 2 / KeePassXC XML export
+///
+/// Entries take their folder from the enclosing groups, minus the root group
+/// (the database itself). `History` snapshots and the `Recycle Bin` group are
+/// skipped. Values still marked `Protected` come from a raw KDBX dump rather
+/// than an export and are rejected.
+pub fn parse(content: &str) -> Result<Vec<Item>> {
+    let mut reader = Reader::from_str(content);
+
+    let mut path: Vec<String> = Vec::new();
+    let mut groups: Vec<String> = Vec::new();
+    let mut entry: Option<Item> = None;
+    let mut key = String::new();
+    let mut value = String::new();
+    let mut items = Vec::new();
+
+    loop {
+        let event = reader.read_event().map_err(|e| {
+            Error::ImportError(format!(
+                "Invalid KeePass XML at byte {}: {}",
+                reader.error_position(),
+                e
+            ))
+        })?;
+
+        match event {
+            Event::Start(start) => {
+                let name = String::from_utf8_lossy(start.name().as_ref()).into_owned();
+                match name.as_str() {
+                    "Group" => groups.push(String::new()),
+                    "Entry" if !path.iter().any(|p| p == "History") => {
+                        entry = Some(Item::default());
+                    }
+                    "String" => {
+                        key.clear();
+                        value.clear();
+                    }
+                    "Value" if entry.is_some() => {
+                        let protected = start.attributes().flatten().any(|a| {
+                            a.key.as_ref() == b"Protected" && a.value.as_ref() == b"True"
+                        });
+                        if protected {
+                            return Err(Error::ImportError(
+                                "Protected values found; use KeePass's XML export, not a raw database dump"
+                                    .to_string(),
+                            ));
+                        }
+                    }
+                    _ => {}
+                }
+                path.push(name);
+            }
+            Event::Text(text) => {
+                let text = text
+                    .unescape()
+                    .map_err(|e| Error::ImportError(format!("Invalid KeePass XML: {}", e)))?;
+                let parent = path.len().checked_sub(2).map(|i| path[i].as_str());
+                match (parent, path.last().map(String::as_str)) {
+                    (Some("Group"), Some("Name")) => {
+                        if let Some(group) = groups.last_mut() {
+                            group.push_str(&text);
+                        }
+                    }
+                    (Some("String"), Some("Key")) => key.push_str(&text),
+                    (Some("String"), Some("Value")) => value.push_str(&text),
+                    _ => {}
+                }
+            }
+            Event::End(end) => {
+                path.pop();
+                match end.name().as_ref() {
+                    b"Group" => {
+                        groups.pop();
+                    }
+                    b"String" if path.last().map(String::as_str) == Some("Entry") => {
+                        if let Some(item) = entry.as_mut().filter(|_| !path.iter().any(|p| p == "History")) {
+                            push_field(item, &key, &value);
+                        }
+                    }
+                    b"Entry" if !path.iter().any(|p| p == "History") => {
+                        if let Some(mut item) = entry.take() {
+                            if !groups.iter().any(|g| g == "Recycle Bin") {
+                                item.folder = groups.iter().skip(1).cloned().collect::<Vec<_>>().join("/");
+                                items.push(item);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(items)
+}
+
+fn push_field(item: &mut Item, key: &str, value: &str) {
+    match key {
+        "Title" => item.title = value.to_string(),
+        "UserName" => item.fields.push(Field::builtin("username", value)),
+        "Password" => item.fields.push(Field::builtin("password", value)),
+        "URL" => item.fields.push(Field::builtin("url", value)),
+        "Notes" => item.fields.push(Field::builtin("notes", value)),
+        "otp" | "TOTP Seed" => item.fields.push(Field::builtin("totp", value)),
+        _ => item.fields.push(Field::custom(key, value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::fixture_path;
+    use std::fs;
+
+    #[test]
+    fn test_parses_entries_groups_and_custom_strings() {
+        let content = fs::read_to_string(fixture_path("keepass.xml")).unwrap();
+        let items = parse(&content).unwrap();
+
+        assert_eq!(items.len(), 2, "history and recycle bin must be skipped");
+        assert_eq!(items[0].folder, "");
+        assert_eq!(items[0].title, "Sentry");
+        assert_eq!(
+            items[0].fields,
+            vec![
+                Field::builtin("username", "ci"),
+                Field::builtin("password", "sentry & <pw>"),
+                Field::custom("DSN", "https://key@sentry.io/1"),
+                Field::builtin("notes", ""),
+            ]
+        );
+        assert_eq!(items[1].folder, "Cloud");
+        assert_eq!(items[1].fields, vec![Field::builtin("password", "cf-token")]);
+    }
+
+    #[test]
+    fn test_rejects_protected_values_and_bad_xml() {
+        let raw = "<KeePassFile><Root><Group><Name>db</Name><Entry><String><Key>Password</Key>\
+                   <Value Protected=\"True\">c2FsdA==</Value></String></Entry></Group></Root></KeePassFile>";
+        assert!(parse(raw).is_err());
+        assert!(parse("<KeePassFile><Root></Group>").is_err());
+    }
+}
@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::config::{is_valid_key, ParseOptions};
+use crate::error::{Error, Result};
+
+pub mod bitwarden;
+pub mod compose;
+pub mod csv_export;
+pub mod items;
+pub mod k8s;
+pub mod keepass;
+pub mod onepassword;
+pub mod pass;
+pub mod structured;
+
+pub use items::ItemRule;
+
+/// Source formats understood by `import --from`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Yaml,
+    Toml,
+    /// `docker-compose.yml`: the dotenv files named by `env_file`
+    Compose,
+    /// Kubernetes `Secret` manifests (`data` and `stringData`)
+    K8s,
+    /// Unencrypted Bitwarden JSON export
+    Bitwarden,
+    /// 1Password CSV export
+    OnePasswordCsv,
+    /// 1Password `.1pux` export (a zip holding `export.data`)
+    OnePux,
+    /// KeePass 2 / KeePassXC XML export
+    KeepassXml,
+    /// KeePass 2 / KeePassXC CSV export
+    KeepassCsv,
+    /// `pass`-style directory tree of plain-text (already decrypted) files
+    Pass,
+}
+
+impl Format {
+    /// Why every value from this format counts as sensitive, if it does
+    ///
+    /// Secret manifests and password managers only hold things someone
+    /// already decided to keep secret, so the name/value classifier is
+    /// bypassed for them.
+    pub fn sensitive_reason(&self) -> Option<&'static str> {
+        match self {
+            Format::K8s => Some("value of a Kubernetes Secret"),
+            Format::Bitwarden
+            | Format::OnePasswordCsv
+            | Format::OnePux
+            | Format::KeepassXml
+            | Format::KeepassCsv
+            | Format::Pass => Some("entry in a password manager export"),
+            Format::Json | Format::Yaml | Format::Toml | Format::Compose => None,
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(Format::Json),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            "toml" => Ok(Format::Toml),
+            "compose" | "docker-compose" => Ok(Format::Compose),
+            "k8s" | "kubernetes" => Ok(Format::K8s),
+            "bitwarden" => Ok(Format::Bitwarden),
+            "1password-csv" => Ok(Format::OnePasswordCsv),
+            "1pux" => Ok(Format::OnePux),
+            "keepass-xml" | "keepass" => Ok(Format::KeepassXml),
+            "keepass-csv" => Ok(Format::KeepassCsv),
+            "pass" => Ok(Format::Pass),
+            _ => Err(Error::ImportError(format!(
+                "Unknown format: {}. Use json, yaml, toml, compose, k8s, bitwarden, \
+                 1password-csv, 1pux, keepass-xml, keepass-csv or pass",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Format::Json => "json",
+            Format::Yaml => "yaml",
+            Format::Toml => "toml",
+            Format::Compose => "compose",
+            Format::K8s => "k8s",
+            Format::Bitwarden => "bitwarden",
+            Format::OnePasswordCsv => "1password-csv",
+            Format::OnePux => "1pux",
+            Format::KeepassXml => "keepass-xml",
+            Format::KeepassCsv => "keepass-csv",
+            Format::Pass => "pass",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Letter case applied to flattened key names
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyCase {
+    Upper,
+    Lower,
+    Preserve,
+}
+
+impl FromStr for KeyCase {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "upper" => Ok(KeyCase::Upper),
+            "lower" => Ok(KeyCase::Lower),
+            "preserve" => Ok(KeyCase::Preserve),
+            _ => Err(Error::ImportError(format!(
+                "Unknown key case: {}. Use upper, lower or preserve",
+                s
+            ))),
+        }
+    }
+}
+
+impl KeyCase {
+    pub fn apply(&self, name: String) -> String {
+        match self {
+            KeyCase::Upper => name.to_ascii_uppercase(),
+            KeyCase::Lower => name.to_ascii_lowercase(),
+            KeyCase::Preserve => name,
+        }
+    }
+}
+
+/// How a path of nested keys becomes one variable name
+///
+/// Every character outside `[A-Za-z0-9_]` in a segment becomes `_`, the
+/// segments are joined with `separator` and the result is re-cased, so with
+/// the defaults both `{"db": {"password": ..}}` and `{"db.password": ..}`
+/// map to `DB_PASSWORD`.
+#[derive(Debug, Clone)]
+pub struct KeyRule {
+    pub separator: String,
+    pub case: KeyCase,
+}
+
+impl Default for KeyRule {
+    fn default() -> Self {
+        KeyRule {
+            separator: "_".to_string(),
+            case: KeyCase::Upper,
+        }
+    }
+}
+
+impl KeyRule {
+    pub fn key<S: AsRef<str>>(&self, path: &[S]) -> String {
+        let joined = path
+            .iter()
+            .map(|segment| {
+                segment
+                    .as_ref()
+                    .chars()
+                    .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join(&self.separator);
+
+        self.case.apply(joined)
+    }
+}
+
+/// Read `path` as `format` and return `(key, value)` pairs in file order
+///
+/// Later pairs override earlier ones with the same key (compose `env_file`
+/// lists rely on this); the other formats reject keys that collide after
+/// mapping instead. `rule` names keys in structured documents and Secret
+/// manifests, `item_rule` names password manager entries.
+pub fn read(
+    path: &Path,
+    format: Format,
+    rule: &KeyRule,
+    item_rule: &ItemRule,
+    options: ParseOptions,
+) -> Result<Vec<(String, String)>> {
+    let text = || fs::read_to_string(path).map_err(Error::IoError);
+
+    let pairs = match format {
+        Format::Json => structured::flatten(&structured::from_json(&text()?)?, rule)?,
+        Format::Yaml => structured::flatten(&structured::from_yaml(&text()?)?, rule)?,
+        Format::Toml => structured::flatten(&structured::from_toml(&text()?)?, rule)?,
+        Format::Compose => {
+            let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+            compose::parse(&text()?, base_dir, options)?
+        }
+        Format::K8s => k8s::parse(&text()?, rule)?,
+        Format::Bitwarden => item_rule.pairs(&bitwarden::parse(&text()?)?, rule.case)?,
+        Format::OnePasswordCsv | Format::KeepassCsv => {
+            item_rule.pairs(&csv_export::parse(&text()?)?, rule.case)?
+        }
+        Format::OnePux => item_rule.pairs(&onepassword::parse_1pux(path)?, rule.case)?,
+        Format::KeepassXml => item_rule.pairs(&keepass::parse(&text()?)?, rule.case)?,
+        Format::Pass => item_rule.pairs(&pass::parse(path)?, rule.case)?,
+    };
+
+    for (key, _) in &pairs {
+        validate_key(key)?;
+    }
+
+    Ok(pairs)
+}
+
+/// Fail on the first key produced twice, naming where both came from
+fn reject_collisions(origins: &[(String, String)]) -> Result<()> {
+    let mut seen: HashMap<&str, &str> = HashMap::new();
+    for (key, origin) in origins {
+        if let Some(first) = seen.insert(key, origin) {
+            return Err(Error::ImportError(format!(
+                "{} and {} both map to {}",
+                first, origin, key
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Keys must be usable as environment variable names
+fn validate_key(key: &str) -> Result<()> {
+    if is_valid_key(key) {
+        Ok(())
+    } else {
+        Err(Error::ImportError(format!(
+            "{:?} is not a valid variable name",
+            key
+        )))
+    }
+}
+
+/// Password manager export fixtures under `tests/fixtures/import`
+#[cfg(test)]
+fn fixture_path(name: &str) -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/import")
+        .join(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_rule_flattens_and_cases() {
+        let rule = KeyRule::default();
+        assert_eq!(rule.key(&["db", "password"]), "DB_PASSWORD");
+        assert_eq!(rule.key(&["db.password"]), "DB_PASSWORD");
+        assert_eq!(rule.key(&["api-keys", "0"]), "API_KEYS_0");
+
+        let rule = KeyRule {
+            separator: "__".to_string(),
+            case: KeyCase::Preserve,
+        };
+        assert_eq!(rule.key(&["Db", "password"]), "Db__password");
+    }
+
+    #[test]
+    fn test_rejects_unusable_names() {
+        assert!(validate_key("DB_PASSWORD").is_ok());
+        assert!(validate_key("_PRIVATE").is_ok());
+        assert!(validate_key("0_TOKEN").is_err());
+        assert!(validate_key("").is_err());
+    }
+}
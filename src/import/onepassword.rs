@@ -0,0 +1,137 @@
+use serde_json::Value;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use super::items::{Field, Item};
+use crate::error::{Error, Result};
+
+/// Parse a 1Password `.1pux` export
+///
+/// The archive's `export.data` holds accounts → vaults → items. Each vault
+/// name becomes the folder; login fields, the `password` of Password items,
+/// notes and every section field are read. Archived items are skipped.
+pub fn parse_1pux(path: &Path) -> Result<Vec<Item>> {
+    let file = File::open(path).map_err(Error::IoError)?;
+    let mut archive = zip::ZipArchive::new(file)
+        .map_err(|e| Error::ImportError(format!("Invalid 1PUX archive: {}", e)))?;
+    let mut data = String::new();
+    archive
+        .by_name("export.data")
+        .map_err(|e| Error::ImportError(format!("Invalid 1PUX archive: {}", e)))?
+        .read_to_string(&mut data)
+        .map_err(Error::IoError)?;
+
+    parse_export_data(&data)
+}
+
+fn parse_export_data(content: &str) -> Result<Vec<Item>> {
+    let export: Value = serde_json::from_str(content)
+        .map_err(|e| Error::ImportError(format!("Invalid 1PUX export data: {}", e)))?;
+
+    let mut items = Vec::new();
+    for account in array(&export, "accounts") {
+        for vault in array(account, "vaults") {
+            let folder = text(vault.pointer("/attrs/name"));
+            for entry in array(vault, "items") {
+                if entry.get("state").and_then(Value::as_str) == Some("archived") {
+                    continue;
+                }
+                items.push(Item {
+                    folder: folder.clone(),
+                    title: text(entry.pointer("/overview/title")),
+                    fields: fields(entry),
+                });
+            }
+        }
+    }
+
+    Ok(items)
+}
+
+fn fields(entry: &Value) -> Vec<Field> {
+    let mut fields = Vec::new();
+    let details = entry.get("details").unwrap_or(&Value::Null);
+
+    for login in array(details, "loginFields") {
+        let value = text(login.get("value"));
+        match login.get("designation").and_then(Value::as_str) {
+            Some(designation @ ("username" | "password")) => {
+                fields.push(Field::builtin(designation, &value))
+            }
+            _ => fields.push(Field::custom(&text(login.get("name")), &value)),
+        }
+    }
+    if let Some(password) = details.get("password").and_then(Value::as_str) {
+        fields.push(Field::builtin("password", password));
+    }
+    if let Some(url) = entry.pointer("/overview/url").and_then(Value::as_str) {
+        fields.push(Field::builtin("url", url));
+    }
+    if let Some(notes) = details.get("notesPlain").and_then(Value::as_str) {
+        fields.push(Field::builtin("notes", notes));
+    }
+
+    for section in array(details, "sections") {
+        for field in array(section, "fields") {
+            let mut name = text(field.get("title"));
+            if name.is_empty() {
+                name = text(field.get("id"));
+            }
+            // `value` is a single-key object naming the field type
+            let value = field
+                .get("value")
+                .and_then(Value::as_object)
+                .and_then(|typed| typed.values().next());
+            if let Some(value @ (Value::String(_) | Value::Number(_) | Value::Bool(_))) = value {
+                fields.push(Field::custom(&name, &text(Some(value))));
+            }
+        }
+    }
+
+    fields
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> impl Iterator<Item = &'a Value> {
+    value.get(key).and_then(Value::as_array).into_iter().flatten()
+}
+
+fn text(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::fixture_path;
+
+    #[test]
+    fn test_parses_1pux_archive() {
+        let items = parse_1pux(&fixture_path("export.1pux")).unwrap();
+
+        assert_eq!(items.len(), 2, "archived item must be skipped");
+        assert_eq!(items[0].folder, "Private");
+        assert_eq!(items[0].title, "Slack");
+        assert_eq!(
+            items[0].fields,
+            vec![
+                Field::builtin("username", "me@example.com"),
+                Field::builtin("password", "slack-pw"),
+                Field::builtin("url", "https://slack.com"),
+                Field::builtin("notes", ""),
+                Field::custom("bot token", "xoxb-1pux"),
+                Field::custom("workspace", "acme"),
+            ]
+        );
+        assert_eq!(items[1].fields, vec![Field::builtin("password", "dd-api-key")]);
+    }
+
+    #[test]
+    fn test_rejects_non_archives() {
+        assert!(parse_1pux(&fixture_path("bitwarden.json")).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use std::fs;
+use std::path::Path;
+
+use super::items::{Field, Item};
+use crate::error::{Error, Result};
+
+/// Read a `pass`-style tree of plain-text entries
+///
+/// Every file is one entry: the first line is the password, `name: value`
+/// lines are fields (`login`/`user`/`username` and `url`/`website` map to
+/// the built-ins, `otpauth://` lines to `totp`) and anything else is notes.
+/// The relative directory is the folder and the file name (minus `.txt`) the
+/// title. Hidden files such as `.gpg-id` are ignored; `.gpg` files have to
+/// be decrypted first.
+pub fn parse(root: &Path) -> Result<Vec<Item>> {
+    if !root.is_dir() {
+        return Err(Error::ImportError(format!(
+            "{} is not a directory",
+            root.display()
+        )));
+    }
+
+    let mut items = Vec::new();
+    walk(root, "", &mut items)?;
+    Ok(items)
+}
+
+fn walk(dir: &Path, folder: &str, items: &mut Vec<Item>) -> Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .map_err(Error::IoError)?
+        .collect::<std::io::Result<_>>()
+        .map_err(Error::IoError)?;
+    entries.sort_by_key(|e| e.file_name());
+
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+
+        if path.is_dir() {
+            let nested = if folder.is_empty() {
+                name
+            } else {
+                format!("{}/{}", folder, name)
+            };
+            walk(&path, &nested, items)?;
+        } else if name.ends_with(".gpg") {
+            return Err(Error::ImportError(format!(
+                "{} is encrypted; decrypt the store to plain files first",
+                path.display()
+            )));
+        } else {
+            let content = fs::read_to_string(&path).map_err(|e| {
+                Error::ImportError(format!("Failed to read {}: {}", path.display(), e))
+            })?;
+            items.push(Item {
+                folder: folder.to_string(),
+                title: name.strip_suffix(".txt").unwrap_or(&name).to_string(),
+                fields: fields(&content),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+fn fields(content: &str) -> Vec<Field> {
+    let mut lines = content.lines();
+    let mut fields = vec![Field::builtin("password", lines.next().unwrap_or(""))];
+    let mut notes = Vec::new();
+
+    for line in lines {
+        if line.starts_with("otpauth://") {
+            fields.push(Field::builtin("totp", line));
+            continue;
+        }
+        match line.split_once(':') {
+            Some((name, value)) if !name.is_empty() && !name.contains(char::is_whitespace) => {
+                let value = value.trim();
+                match name.to_ascii_lowercase().as_str() {
+                    "login" | "user" | "username" => fields.push(Field::builtin("username", value)),
+                    "url" | "website" => fields.push(Field::builtin("url", value)),
+                    _ => fields.push(Field::custom(name, value)),
+                }
+            }
+            _ => notes.push(line),
+        }
+    }
+
+    if !notes.is_empty() {
+        fields.push(Field::builtin("notes", &notes.join("\n")));
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::fixture_path;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parses_tree_and_multiline_entries() {
+        let items = parse(&fixture_path("pass")).unwrap();
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].folder, "");
+        assert_eq!(items[0].title, "github");
+        assert_eq!(items[0].fields, vec![Field::builtin("password", "ghp_pass_token")]);
+
+        assert_eq!(items[1].folder, "work/payments");
+        assert_eq!(items[1].title, "postgres");
+        assert_eq!(
+            items[1].fields,
+            vec![
+                Field::builtin("password", "pass-db-pw"),
+                Field::builtin("username", "dbadmin"),
+                Field::builtin("url", "postgres://db.internal"),
+                Field::custom("replica_token", "rt-123"),
+                Field::builtin("totp", "otpauth://totp/db?secret=ABC"),
+                Field::builtin("notes", "free text note"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_refuses_encrypted_entries() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("github.gpg"), b"\x85\x01").unwrap();
+        let err = parse(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("decrypt"), "{}", err);
+    }
+}
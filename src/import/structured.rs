@@ -0,0 +1,158 @@
+use serde_json::Value;
+
+use super::{reject_collisions, KeyRule};
+use crate::error::{Error, Result};
+
+pub fn from_json(content: &str) -> Result<Value> {
+    serde_json::from_str(content).map_err(|e| Error::ImportError(format!("Invalid JSON: {}", e)))
+}
+
+pub fn from_yaml(content: &str) -> Result<Value> {
+    serde_yaml::from_str(content).map_err(|e| Error::ImportError(format!("Invalid YAML: {}", e)))
+}
+
+pub fn from_toml(content: &str) -> Result<Value> {
+    let table: toml::Table =
+        toml::from_str(content).map_err(|e| Error::ImportError(format!("Invalid TOML: {}", e)))?;
+    Ok(toml_to_json(toml::Value::Table(table)))
+}
+
+/// Convert TOML to the JSON model, keeping datetimes as their TOML text
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Value::from(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(k, v)| (k, toml_to_json(v)))
+                .collect(),
+        ),
+    }
+}
+
+/// Flatten a document into `(key, value)` pairs
+///
+/// Objects and arrays contribute a path segment per level (array elements
+/// use their index); strings are taken verbatim, numbers and booleans in
+/// their JSON spelling, and nulls are dropped.
+pub fn flatten(document: &Value, rule: &KeyRule) -> Result<Vec<(String, String)>> {
+    if !document.is_object() {
+        return Err(Error::ImportError(
+            "Top level must be an object/table of keys".to_string(),
+        ));
+    }
+
+    let mut leaves = Vec::new();
+    walk(document, &mut Vec::new(), &mut leaves);
+
+    let origins: Vec<(String, String)> = leaves
+        .iter()
+        .map(|(path, _)| (rule.key(path), path.join(".")))
+        .collect();
+    reject_collisions(&origins)?;
+
+    Ok(origins
+        .into_iter()
+        .zip(leaves)
+        .map(|((key, _), (_, value))| (key, value))
+        .collect())
+}
+
+fn walk(value: &Value, path: &mut Vec<String>, leaves: &mut Vec<(Vec<String>, String)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                path.push(key.clone());
+                walk(child, path, leaves);
+                path.pop();
+            }
+        }
+        Value::Array(items) => {
+            for (i, child) in items.iter().enumerate() {
+                path.push(i.to_string());
+                walk(child, path, leaves);
+                path.pop();
+            }
+        }
+        Value::Null => {}
+        Value::String(s) => leaves.push((path.clone(), s.clone())),
+        other => leaves.push((path.clone(), other.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(document: Value) -> Vec<(String, String)> {
+        flatten(&document, &KeyRule::default()).unwrap()
+    }
+
+    fn pair(key: &str, value: &str) -> (String, String) {
+        (key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn test_json_nested_keys_and_scalars() {
+        let document = from_json(
+            r#"{"db": {"password": "hunter2", "port": 5432, "tls": true, "replica": null},
+                "servers": [{"token": "t0"}, {"token": "t1"}]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            pairs(document),
+            vec![
+                pair("DB_PASSWORD", "hunter2"),
+                pair("DB_PORT", "5432"),
+                pair("DB_TLS", "true"),
+                pair("SERVERS_0_TOKEN", "t0"),
+                pair("SERVERS_1_TOKEN", "t1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_yaml_dotted_and_nested_keys() {
+        let document = from_yaml("db.password: hunter2\napi:\n  stripe-key: sk_live_1\n").unwrap();
+
+        assert_eq!(
+            pairs(document),
+            vec![pair("API_STRIPE_KEY", "sk_live_1"), pair("DB_PASSWORD", "hunter2")]
+        );
+    }
+
+    #[test]
+    fn test_toml_tables_and_datetimes() {
+        let document = from_toml(
+            "[database]\npassword = \"hunter2\"\nrotated = 2026-01-02T03:04:05Z\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            pairs(document),
+            vec![
+                pair("DATABASE_PASSWORD", "hunter2"),
+                pair("DATABASE_ROTATED", "2026-01-02T03:04:05Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_colliding_paths_are_rejected() {
+        let document = from_json(r#"{"db.password": "a", "db": {"password": "b"}}"#).unwrap();
+        let err = flatten(&document, &KeyRule::default()).unwrap_err();
+        assert!(err.to_string().contains("DB_PASSWORD"), "{}", err);
+    }
+
+    #[test]
+    fn test_top_level_must_be_an_object() {
+        assert!(flatten(&from_json("[1, 2]").unwrap(), &KeyRule::default()).is_err());
+        assert!(from_yaml("key: [unclosed").is_err());
+    }
+}
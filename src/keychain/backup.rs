@@ -0,0 +1,128 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+use super::storage::replace_private;
+use super::vault::{decode_field, KdfParams};
+use crate::error::{Error, Result};
+
+/// Environment variable consulted for the backup passphrase
+pub const BACKUP_PASSPHRASE_ENV: &str = "KEYCHAIN_BACKUP_PASSPHRASE";
+
+const BACKUP_VERSION: u32 = 1;
+const AAD: &[u8] = b"keychain-cli:backup:v1";
+
+/// Passphrase-sealed copy of a file, written by `setup --scrub-backup`
+///
+/// Same scheme as the file vault (Argon2id, XChaCha20-Poly1305) but holding
+/// raw bytes, so the original can be restored exactly.
+#[derive(Serialize, Deserialize)]
+struct BackupFile {
+    version: u32,
+    kdf: KdfParams,
+    cipher: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Encrypt `plaintext` to a new file at `path`; never overwrites
+pub fn seal(path: &Path, plaintext: &[u8], passphrase: &str) -> Result<()> {
+    if path.exists() {
+        return Err(Error::KeychainError(format!(
+            "Backup {} already exists",
+            path.display()
+        )));
+    }
+
+    let kdf = KdfParams::generate(19 * 1024, 2, 1);
+    let key = kdf.derive_key(passphrase)?;
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = XChaCha20Poly1305::new(key.as_ref().into())
+        .encrypt(&nonce, Payload { msg: plaintext, aad: AAD })
+        .map_err(|_| Error::KeychainError("Failed to encrypt backup".to_string()))?;
+
+    let file = BackupFile {
+        version: BACKUP_VERSION,
+        kdf,
+        cipher: "xchacha20poly1305".to_string(),
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(ciphertext),
+    };
+    replace_private(path, serde_json::to_string_pretty(&file)?.as_bytes())
+}
+
+/// Decrypt a backup written by `seal`
+pub fn open(path: &Path, passphrase: &str) -> Result<Zeroizing<Vec<u8>>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| Error::KeychainError(format!("Failed to read backup: {}", e)))?;
+    let file: BackupFile = serde_json::from_str(&content)
+        .map_err(|e| Error::KeychainError(format!("Backup file is corrupted: {}", e)))?;
+    if file.version != BACKUP_VERSION {
+        return Err(Error::KeychainError(format!(
+            "Unsupported backup version: {}",
+            file.version
+        )));
+    }
+
+    let key = file.kdf.derive_key(passphrase)?;
+    let nonce = decode_field(&file.nonce, "nonce")?;
+    let ciphertext = decode_field(&file.ciphertext, "ciphertext")?;
+    if nonce.len() != 24 {
+        return Err(Error::KeychainError("Backup nonce is corrupted".to_string()));
+    }
+
+    XChaCha20Poly1305::new(key.as_ref().into())
+        .decrypt(XNonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: AAD })
+        .map(Zeroizing::new)
+        .map_err(|_| {
+            Error::KeychainError(
+                "Failed to decrypt backup (wrong passphrase or corrupted file)".to_string(),
+            )
+        })
+}
+
+/// Backup passphrase from the environment, otherwise a prompt
+///
+/// `confirm` asks twice, for new backups.
+pub fn read_passphrase(confirm: bool) -> Result<Zeroizing<String>> {
+    let passphrase = match std::env::var(BACKUP_PASSPHRASE_ENV) {
+        Ok(passphrase) => passphrase,
+        Err(_) => {
+            let mut prompt = dialoguer::Password::new().with_prompt("Backup passphrase");
+            if confirm {
+                prompt = prompt.with_confirmation("Confirm passphrase", "Passphrases don't match");
+            }
+            prompt
+                .interact()
+                .map_err(|e| Error::KeychainError(format!("Failed to read passphrase: {}", e)))?
+        }
+    };
+
+    if passphrase.is_empty() {
+        return Err(Error::ValidationError("Backup passphrase must not be empty".to_string()));
+    }
+    Ok(Zeroizing::new(passphrase))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_seal_and_open() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("env.backup");
+        seal(&path, b"TOKEN=sk-live\n", "pass").unwrap();
+
+        assert!(!fs::read_to_string(&path).unwrap().contains("sk-live"));
+        assert_eq!(open(&path, "pass").unwrap().as_slice(), b"TOKEN=sk-live\n");
+        assert!(open(&path, "wrong").is_err());
+        assert!(seal(&path, b"other", "pass").is_err(), "must not overwrite");
+    }
+}
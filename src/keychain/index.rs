@@ -0,0 +1,213 @@
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+
+use super::storage::{write_private, FileLock};
+use crate::error::{Error, Result};
+
+const INDEX_VERSION: u32 = 1;
+
+/// Metadata recorded for each stored key (never the value itself)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyEntry {
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl KeyEntry {
+    fn new(now: DateTime<Utc>) -> Self {
+        KeyEntry {
+            created_at: now,
+            updated_at: now,
+            source: None,
+            tags: Vec::new(),
+            description: None,
+            expires_at: None,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|t| t <= Utc::now())
+    }
+}
+
+/// Versioned on-disk manifest: `~/.keychain/<service>.index.json`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    pub keys: BTreeMap<String, KeyEntry>,
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Manifest {
+            version: INDEX_VERSION,
+            keys: BTreeMap::new(),
+        }
+    }
+}
+
+/// Index of the keys stored for a service, with per-key metadata
+///
+/// Backends don't all support enumerating their items, so every store keeps
+/// this manifest up to date. Legacy `<service>.keys` name lists are migrated
+/// on first read. Every access holds `<service>.index.json.lock`, and writes
+/// go through a temp file + rename so a crash never leaves a truncated index.
+pub struct KeyIndex {
+    service_name: String,
+}
+
+impl KeyIndex {
+    pub fn new(service_name: &str) -> Self {
+        KeyIndex {
+            service_name: service_name.to_string(),
+        }
+    }
+
+    /// Get the path to the index manifest
+    fn get_index_file(&self) -> PathBuf {
+        super::keychain_dir().join(format!("{}.index.json", self.service_name))
+    }
+
+    /// Get the path to the legacy newline-separated keys file
+    fn get_legacy_keys_file(&self) -> PathBuf {
+        super::keychain_dir().join(format!("{}.keys", self.service_name))
+    }
+
+    /// Hold the index lock for a read-modify-write cycle
+    fn lock(&self) -> Result<FileLock> {
+        FileLock::acquire(&self.get_index_file())
+    }
+
+    /// Load the manifest, migrating a legacy `.keys` file if needed
+    pub fn load(&self) -> Result<Manifest> {
+        let _lock = self.lock()?;
+        self.load_locked()
+    }
+
+    fn load_locked(&self) -> Result<Manifest> {
+        let index_file = self.get_index_file();
+
+        if !index_file.exists() {
+            return self.migrate_legacy();
+        }
+
+        let content = fs::read_to_string(&index_file)
+            .map_err(|e| Error::KeychainError(format!("Failed to read index file: {}", e)))?;
+        let manifest: Manifest = serde_json::from_str(&content)
+            .map_err(|e| Error::KeychainError(format!("Index file is corrupted: {}", e)))?;
+
+        if manifest.version > INDEX_VERSION {
+            return Err(Error::KeychainError(format!(
+                "Index version {} is newer than this keychain-cli supports ({})",
+                manifest.version, INDEX_VERSION
+            )));
+        }
+
+        Ok(manifest)
+    }
+
+    fn save(&self, manifest: &Manifest) -> Result<()> {
+        write_private(&self.get_index_file(), serde_json::to_string_pretty(manifest)?.as_bytes())
+    }
+
+    /// Convert a legacy `.keys` file into a manifest
+    fn migrate_legacy(&self) -> Result<Manifest> {
+        let legacy_file = self.get_legacy_keys_file();
+        let mut manifest = Manifest::default();
+
+        if !legacy_file.exists() {
+            return Ok(manifest);
+        }
+
+        let content = fs::read_to_string(&legacy_file)
+            .map_err(|e| Error::KeychainError(format!("Failed to read keys file: {}", e)))?;
+
+        // The old format has no timestamps; the file's mtime is the best guess
+        let migrated_at = fs::metadata(&legacy_file)
+            .and_then(|m| m.modified())
+            .map(DateTime::<Utc>::from)
+            .unwrap_or_else(|_| Utc::now());
+
+        for key in content.lines().map(str::trim).filter(|k| !k.is_empty()) {
+            manifest.keys.insert(key.to_string(), KeyEntry::new(migrated_at));
+        }
+
+        self.save(&manifest)?;
+        fs::rename(&legacy_file, legacy_file.with_extension("keys.migrated"))
+            .map_err(|e| Error::KeychainError(format!("Failed to retire keys file: {}", e)))?;
+
+        info!(
+            "Migrated {} keys from {} to {}",
+            manifest.keys.len(),
+            legacy_file.display(),
+            self.get_index_file().display()
+        );
+        Ok(manifest)
+    }
+
+    /// Record that a key was stored, creating or refreshing its entry
+    ///
+    /// Backends call this once the secret is written, so a failure is only
+    /// logged: the store has the value and `reconcile --fix` can adopt it.
+    pub fn save_key(&self, key: &str) {
+        if let Err(e) = self.update(key, |_| {}) {
+            warn!("{} is stored, but the index could not record it: {}", key, e);
+        }
+    }
+
+    /// Create or refresh an entry and apply extra metadata changes to it
+    pub fn update<F>(&self, key: &str, apply: F) -> Result<()>
+    where
+        F: FnOnce(&mut KeyEntry),
+    {
+        let _lock = self.lock()?;
+        let mut manifest = self.load_locked()?;
+        let now = Utc::now();
+
+        let entry = manifest
+            .keys
+            .entry(key.to_string())
+            .or_insert_with(|| KeyEntry::new(now));
+        entry.updated_at = now;
+        apply(entry);
+
+        debug!("Updated index entry for {}", key);
+        self.save(&manifest)
+    }
+
+    /// Drop a key's entry; returns whether it was indexed
+    pub fn remove(&self, key: &str) -> Result<bool> {
+        let _lock = self.lock()?;
+        let mut manifest = self.load_locked()?;
+
+        if manifest.keys.remove(key).is_none() {
+            return Ok(false);
+        }
+
+        debug!("Removed index entry for {}", key);
+        self.save(&manifest)?;
+        Ok(true)
+    }
+
+    /// Load all stored key names
+    pub fn load_keys(&self) -> Result<Vec<String>> {
+        Ok(self.load()?.keys.into_keys().collect())
+    }
+
+    /// Load the tags of every indexed key
+    pub fn load_tags(&self) -> Result<HashMap<String, Vec<String>>> {
+        Ok(self.load()?.keys.into_iter().map(|(key, entry)| (key, entry.tags)).collect())
+    }
+}
@@ -0,0 +1,100 @@
+use log::debug;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use super::index::KeyIndex;
+use super::SecretStore;
+use crate::error::{Error, Result};
+
+/// In-memory store for tests, optionally backed by a JSON fixture
+///
+/// `--backend mock` starts empty and forgets everything on exit.
+/// `--backend mock:<path>` seeds from a `{"KEY": "value"}` file and writes
+/// every change back to it, so separate invocations share state.
+pub struct MockStore {
+    service_name: String,
+    fixture: Option<PathBuf>,
+    secrets: RefCell<BTreeMap<String, String>>,
+    index: KeyIndex,
+}
+
+impl MockStore {
+    pub fn new(service_name: &str, fixture: Option<PathBuf>) -> Result<Self> {
+        let secrets = match &fixture {
+            Some(path) if path.exists() => {
+                let content = fs::read_to_string(path)
+                    .map_err(|e| Error::KeychainError(format!("Failed to read mock fixture: {}", e)))?;
+                serde_json::from_str(&content)?
+            }
+            _ => BTreeMap::new(),
+        };
+
+        Ok(MockStore {
+            service_name: service_name.to_string(),
+            fixture,
+            secrets: RefCell::new(secrets),
+            index: KeyIndex::new(service_name),
+        })
+    }
+
+    /// Write the current contents back to the fixture, if any
+    fn flush(&self) -> Result<()> {
+        if let Some(path) = &self.fixture {
+            let content = serde_json::to_string_pretty(&*self.secrets.borrow())?;
+            fs::write(path, content)
+                .map_err(|e| Error::KeychainError(format!("Failed to write mock fixture: {}", e)))?;
+        }
+        Ok(())
+    }
+}
+
+impl SecretStore for MockStore {
+    fn service_name(&self) -> &str {
+        &self.service_name
+    }
+
+    fn store(&self, key: &str, value: &str) -> Result<()> {
+        debug!("Storing {} in mock store", key);
+        self.secrets.borrow_mut().insert(key.to_string(), value.to_string());
+        self.flush()?;
+        self.index.save_key(key);
+        Ok(())
+    }
+
+    fn retrieve(&self, key: &str) -> Result<String> {
+        self.secrets
+            .borrow()
+            .get(key)
+            .cloned()
+            .ok_or_else(|| Error::KeychainError(format!("Secret not found: {}", key)))
+    }
+
+    fn retrieve_all(&self) -> Result<Vec<(String, String)>> {
+        Ok(self
+            .secrets
+            .borrow()
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect())
+    }
+
+    fn list_stored(&self) -> Result<Vec<String>> {
+        Ok(self.secrets.borrow().keys().cloned().collect())
+    }
+
+    fn try_exists(&self, key: &str) -> Result<bool> {
+        Ok(self.secrets.borrow().contains_key(key))
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        if self.secrets.borrow_mut().remove(key).is_none() {
+            return Err(Error::KeychainError(format!("Secret not found: {}", key)));
+        }
+        self.flush()?;
+
+        self.index.remove(key)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,303 @@
+use log::{debug, warn};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use super::index::KeyIndex;
+use super::SecretStore;
+use crate::error::{Error, Result};
+
+/// `security` exit status for errSecItemNotFound
+const ITEM_NOT_FOUND_STATUS: i32 = 44;
+
+/// Wrapper for macOS Keychain operations via the `security` binary
+pub struct KeychainManager {
+    service_name: String,
+    index: KeyIndex,
+}
+
+impl KeychainManager {
+    pub fn new(service_name: &str) -> Self {
+        KeychainManager {
+            service_name: service_name.to_string(),
+            index: KeyIndex::new(service_name),
+        }
+    }
+}
+
+impl SecretStore for KeychainManager {
+    fn service_name(&self) -> &str {
+        &self.service_name
+    }
+
+    /// Store a secret in Keychain
+    ///
+    /// The value never appears in argv: the command is piped to
+    /// `security -i` on stdin, with the password hex-encoded via `-X`.
+    fn store(&self, key: &str, value: &str) -> Result<()> {
+        debug!("Storing {} in Keychain (service: {})", key, self.service_name);
+
+        let command = format!(
+            "add-generic-password -a {} -s {} -X {} -U\n",
+            quote_arg(&self.service_name),
+            quote_arg(key),
+            hex_encode(value.as_bytes()),
+        );
+
+        let mut child = Command::new("security")
+            .arg("-i")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| Error::KeychainError(format!("Failed to execute security command: {}", e)))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(command.as_bytes())
+                .map_err(|e| Error::KeychainError(format!("Failed to write to security: {}", e)))?;
+        }
+
+        let output = child
+            .wait_with_output()
+            .map_err(|e| Error::KeychainError(format!("Failed to execute security command: {}", e)))?;
+
+        // Interactive mode exits 0 even when a command fails, so stderr counts too
+        if !output.status.success() || !output.stderr.is_empty() {
+            let err_msg = String::from_utf8_lossy(&output.stderr);
+            return Err(Error::KeychainError(format!("Keychain store failed: {}", err_msg)));
+        }
+
+        // Save key to state file
+        self.index.save_key(key);
+
+        debug!("Successfully stored {} in Keychain", key);
+        Ok(())
+    }
+
+    /// Retrieve a secret from Keychain
+    fn retrieve(&self, key: &str) -> Result<String> {
+        debug!("Retrieving {} from Keychain (service: {})", key, self.service_name);
+
+        let output = Command::new("security")
+            .args(["find-generic-password"])
+            .args(["-a", &self.service_name])
+            .args(["-s", key])
+            .args(["-w"])
+            .output()
+            .map_err(|e| Error::KeychainError(format!("Failed to execute security command: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(Error::KeychainError(format!("Secret not found: {}", key)));
+        }
+
+        let value = decode_password(&output.stdout)
+            .map_err(|e| Error::KeychainError(format!("Failed to parse secret: {}", e)))?;
+
+        debug!("Successfully retrieved {} from Keychain", key);
+        Ok(value)
+    }
+
+    /// Retrieve all secrets for this service
+    fn retrieve_all(&self) -> Result<Vec<(String, String)>> {
+        debug!("Retrieving all secrets from Keychain for service: {}", self.service_name);
+
+        // Load keys from state file
+        let keys = self.index.load_keys()?;
+
+        let mut results = Vec::new();
+        for key in keys {
+            match self.retrieve(&key) {
+                Ok(value) => results.push((key, value)),
+                Err(e) => warn!("{} is indexed but could not be retrieved: {}", key, e),
+            }
+        }
+
+        debug!("Retrieved {} secrets from Keychain", results.len());
+        Ok(results)
+    }
+
+    /// List generic passwords for this service from `security dump-keychain`
+    fn list_stored(&self) -> Result<Vec<String>> {
+        // Without -d, dump-keychain prints attributes only, never secret data
+        let output = Command::new("security")
+            .args(["dump-keychain"])
+            .stderr(Stdio::null())
+            .output()
+            .map_err(|e| Error::KeychainError(format!("Failed to execute security command: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(Error::KeychainError("Failed to enumerate Keychain items".to_string()));
+        }
+
+        let dump = String::from_utf8_lossy(&output.stdout);
+        Ok(parse_dump(&dump, &self.service_name))
+    }
+
+    /// Check if a secret exists without reading its value
+    fn try_exists(&self, key: &str) -> Result<bool> {
+        // Without -w/-g, find-generic-password only prints item attributes
+        let output = Command::new("security")
+            .args(["find-generic-password"])
+            .args(["-a", &self.service_name])
+            .args(["-s", key])
+            .stdout(Stdio::null())
+            .output()
+            .map_err(|e| Error::KeychainError(format!("Failed to execute security command: {}", e)))?;
+
+        match output.status.code() {
+            Some(0) => Ok(true),
+            Some(ITEM_NOT_FOUND_STATUS) => Ok(false),
+            _ => Err(Error::KeychainError(format!(
+                "Could not check {}: {}",
+                key,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))),
+        }
+    }
+
+    /// Delete a secret from Keychain
+    fn delete(&self, key: &str) -> Result<()> {
+        debug!("Deleting {} from Keychain", key);
+
+        let output = Command::new("security")
+            .args(["delete-generic-password"])
+            .args(["-a", &self.service_name])
+            .args(["-s", key])
+            .output()
+            .map_err(|e| Error::KeychainError(format!("Failed to delete secret: {}", e)))?;
+
+        if !output.status.success() {
+            let err_msg = String::from_utf8_lossy(&output.stderr);
+            return Err(Error::KeychainError(format!("Failed to delete: {}", err_msg)));
+        }
+
+        self.index.remove(key)?;
+
+        debug!("Successfully deleted {} from Keychain", key);
+        Ok(())
+    }
+}
+
+/// Quote an argument for the `security -i` command parser
+fn quote_arg(arg: &str) -> String {
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Undo `find-generic-password -w` output formatting
+///
+/// `security` adds one newline, and prints the password as hex instead when
+/// it has bytes outside printable ASCII (newlines in a PEM key, UTF-8). Hex
+/// that decodes to printable ASCII, or to something that isn't UTF-8 (all
+/// values are stored from strings), can't have been printed that way, so it
+/// is the literal value.
+fn decode_password(output: &[u8]) -> std::result::Result<String, std::string::FromUtf8Error> {
+    let output = output.strip_suffix(b"\n").unwrap_or(output);
+    let decoded = hex_decode(output)
+        .filter(|bytes| !bytes.iter().all(|b| (0x20..0x7f).contains(b)))
+        .and_then(|bytes| String::from_utf8(bytes).ok());
+    match decoded {
+        Some(value) => Ok(value),
+        None => String::from_utf8(output.to_vec()),
+    }
+}
+
+fn hex_decode(hex: &[u8]) -> Option<Vec<u8>> {
+    if hex.is_empty() || !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    hex.chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+/// Extract the `svce` of every generic password whose `acct` is `service_name`
+fn parse_dump(dump: &str, service_name: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+
+    for item in dump.split("keychain: ").skip(1) {
+        if !item.contains("class: \"genp\"") {
+            continue;
+        }
+
+        let account = blob_attribute(item, "acct");
+        let service = blob_attribute(item, "svce");
+        if let (Some(account), Some(key)) = (account, service) {
+            if account == service_name {
+                keys.push(key);
+            }
+        }
+    }
+
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+/// Read a `"name"<blob>="value"` attribute line from a dump-keychain item
+fn blob_attribute(item: &str, name: &str) -> Option<String> {
+    let prefix = format!("\"{}\"<blob>=\"", name);
+    item.lines()
+        .map(str::trim)
+        .find_map(|line| line.strip_prefix(prefix.as_str()))
+        .and_then(|rest| rest.strip_suffix('"'))
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMP: &str = r#"keychain: "/Users/me/Library/Keychains/login.keychain-db"
+version: 512
+class: "genp"
+attributes:
+    0x00000007 <blob>="API_TOKEN"
+    "acct"<blob>="claude-dev"
+    "svce"<blob>="API_TOKEN"
+keychain: "/Users/me/Library/Keychains/login.keychain-db"
+version: 512
+class: "genp"
+attributes:
+    "acct"<blob>="other-service"
+    "svce"<blob>="OTHER"
+keychain: "/Users/me/Library/Keychains/login.keychain-db"
+version: 512
+class: "inet"
+attributes:
+    "acct"<blob>="claude-dev"
+    "srvr"<blob>="example.com"
+keychain: "/Users/me/Library/Keychains/login.keychain-db"
+version: 512
+class: "genp"
+attributes:
+    "acct"<blob>="claude-dev"
+    "svce"<blob>="DB_PASSWORD"
+"#;
+
+    #[test]
+    fn test_parse_dump_filters_by_account() {
+        assert_eq!(parse_dump(DUMP, "claude-dev"), vec!["API_TOKEN", "DB_PASSWORD"]);
+        assert_eq!(parse_dump(DUMP, "other-service"), vec!["OTHER"]);
+        assert!(parse_dump(DUMP, "missing").is_empty());
+    }
+
+    #[test]
+    fn test_decode_password() {
+        let decode = |output: &[u8]| decode_password(output).unwrap();
+        assert_eq!(decode(b"  padded value \n"), "  padded value ");
+        assert_eq!(decode(b"deadbeef\n"), "deadbeef");
+        assert_eq!(decode(b"\n"), "");
+        assert_eq!(decode(hex_encode(b"line1\nline2\n").as_bytes()), "line1\nline2\n");
+        assert_eq!(decode(format!("{}\n", hex_encode("caf\u{e9}".as_bytes())).as_bytes()), "caf\u{e9}");
+    }
+
+    #[test]
+    fn test_quote_arg() {
+        assert_eq!(quote_arg("plain"), "\"plain\"");
+        assert_eq!(quote_arg(r#"a"b\c"#), r#""a\"b\\c""#);
+    }
+}
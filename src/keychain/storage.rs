@@ -0,0 +1,195 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+
+/// Create a directory (and parents) readable only by the current user
+///
+/// Existing directories are tightened to 0700 as well, so state created by
+/// older versions gets hardened on first write.
+pub fn ensure_private_dir(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir)
+        .map_err(|e| Error::KeychainError(format!("Failed to create keychain dir: {}", e)))?;
+    set_mode(dir, 0o700)
+}
+
+/// Atomically replace `path` with `content`, mode 0600
+///
+/// The data is written to a temporary file in the same directory, synced,
+/// then renamed over the target: readers see either the old or the new file,
+/// never a truncated one.
+pub fn write_private(path: &Path, content: &[u8]) -> Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    ensure_private_dir(dir)?;
+    replace_private(path, content)
+}
+
+/// Like `write_private`, but leaves the permissions of the directory alone
+///
+/// For files outside `~/.keychain`, such as user-chosen backup paths.
+pub fn replace_private(path: &Path, content: &[u8]) -> Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("state");
+    let tmp_path = dir.join(format!(".{}.tmp.{}", file_name, std::process::id()));
+
+    let result = open_private(&tmp_path, true)
+        .and_then(|mut f| {
+            f.write_all(content)?;
+            f.sync_all()
+        })
+        .and_then(|_| fs::rename(&tmp_path, path));
+
+    if let Err(e) = result {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(Error::KeychainError(format!(
+            "Failed to write {}: {}",
+            path.display(),
+            e
+        )));
+    }
+
+    Ok(())
+}
+
+/// Replace `path` with `content`, overwriting the old bytes first
+///
+/// The new content is written and synced to a temporary file and renamed
+/// over `path`; the original, still open from before the rename, is then
+/// overwritten with zeros and synced. If the rename fails the original is
+/// left as it was. This is best effort: copy-on-write filesystems, journals
+/// and SSD wear levelling can still hold old blocks.
+pub fn shred_and_replace(path: &Path, content: &[u8]) -> Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+    let tmp_path = dir.join(format!(".{}.tmp.{}", file_name, std::process::id()));
+
+    let result = open_private(&tmp_path, true)
+        .and_then(|mut f| {
+            f.write_all(content)?;
+            f.sync_all()
+        })
+        .and_then(|_| OpenOptions::new().write(true).open(path))
+        .and_then(|original| fs::rename(&tmp_path, path).map(|_| original));
+
+    let mut original = match result {
+        Ok(original) => original,
+        Err(e) => {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(Error::IoError(e));
+        }
+    };
+
+    let shredded = (|| {
+        let len = original.metadata()?.len();
+        let zeros = [0u8; 8192];
+        let mut written = 0;
+        while written < len {
+            let chunk = zeros.len().min((len - written) as usize);
+            original.write_all(&zeros[..chunk])?;
+            written += chunk as u64;
+        }
+        original.sync_all()
+    })();
+
+    shredded.map_err(|e| {
+        Error::IoError(std::io::Error::new(
+            e.kind(),
+            format!(
+                "{} was replaced, but its old contents could not be overwritten: {}",
+                path.display(),
+                e
+            ),
+        ))
+    })
+}
+
+/// Exclusive advisory lock held until the guard is dropped
+pub struct FileLock {
+    file: File,
+}
+
+impl FileLock {
+    /// Block until the lock file next to `path` (`<path>.lock`) is acquired
+    pub fn acquire(path: &Path) -> Result<Self> {
+        let lock_path = lock_path_for(path);
+        if let Some(dir) = lock_path.parent() {
+            ensure_private_dir(dir)?;
+        }
+
+        let file = open_private(&lock_path, false)
+            .map_err(|e| Error::KeychainError(format!("Failed to open lock file: {}", e)))?;
+        file.lock()
+            .map_err(|e| Error::KeychainError(format!("Failed to lock {}: {}", lock_path.display(), e)))?;
+
+        Ok(FileLock { file })
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+fn lock_path_for(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".lock");
+    path.with_file_name(name)
+}
+
+fn open_private(path: &Path, truncate: bool) -> std::io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(truncate);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .map_err(|e| Error::KeychainError(format!("Failed to set permissions on {}: {}", path.display(), e)))
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_shred_and_replace_zeroes_the_old_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(".env");
+        let old = dir.path().join("old-link");
+        fs::write(&path, "TOKEN=sk-live-1234\n").unwrap();
+        // A second name for the original inode shows what happened to its bytes
+        fs::hard_link(&path, &old).unwrap();
+
+        shred_and_replace(&path, b"TOKEN=keychain://svc/TOKEN\n").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "TOKEN=keychain://svc/TOKEN\n");
+        assert_eq!(fs::read(&old).unwrap(), vec![0u8; 19]);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_shred_and_replace_leaves_the_original_when_it_cannot_open_it() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("missing.env");
+
+        assert!(shred_and_replace(&path, b"new").is_err());
+        assert!(!path.exists());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::storage::{write_private, FileLock};
+use crate::error::{Error, Result};
+
+const TRUST_VERSION: u32 = 1;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TrustFile {
+    version: u32,
+    /// Canonical manifest path -> what was approved
+    manifests: BTreeMap<String, Pin>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Pin {
+    /// SHA-256 of the approved content
+    sha256: String,
+    /// Service the manifest was approved to read from
+    service: String,
+}
+
+/// Project manifests the user has approved, pinned by content hash and the
+/// service they read from
+///
+/// Kept in `~/.keychain/trusted.json`. A manifest is only trusted while its
+/// bytes hash to what was approved and the hook reads the service shown at
+/// approval, so any edit (or a different checkout at the same path, or a
+/// hook with another `--service-name`) needs approving again.
+pub struct TrustStore {
+    path: PathBuf,
+}
+
+impl TrustStore {
+    pub fn new() -> Self {
+        TrustStore {
+            path: super::keychain_dir().join("trusted.json"),
+        }
+    }
+
+    /// Hex SHA-256 of manifest content
+    pub fn hash(content: &[u8]) -> String {
+        Sha256::digest(content).iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    pub fn is_trusted(&self, manifest: &Path, hash: &str, service: &str) -> Result<bool> {
+        Ok(self.load()?.manifests.get(&key(manifest)) == Some(&pin(hash, service)))
+    }
+
+    /// Pin `manifest` at `hash`, reading from `service`
+    pub fn allow(&self, manifest: &Path, hash: &str, service: &str) -> Result<()> {
+        let _lock = FileLock::acquire(&self.path)?;
+        let mut file = self.load()?;
+        file.manifests.insert(key(manifest), pin(hash, service));
+        self.save(&file)
+    }
+
+    /// Forget `manifest`; returns whether it was trusted
+    pub fn revoke(&self, manifest: &Path) -> Result<bool> {
+        let _lock = FileLock::acquire(&self.path)?;
+        let mut file = self.load()?;
+        let removed = file.manifests.remove(&key(manifest)).is_some();
+        if removed {
+            self.save(&file)?;
+        }
+        Ok(removed)
+    }
+
+    fn load(&self) -> Result<TrustFile> {
+        if !self.path.exists() {
+            return Ok(TrustFile {
+                version: TRUST_VERSION,
+                ..TrustFile::default()
+            });
+        }
+
+        let content = fs::read_to_string(&self.path)
+            .map_err(|e| Error::KeychainError(format!("Failed to read trust file: {}", e)))?;
+        let file: TrustFile = serde_json::from_str(&content)
+            .map_err(|e| Error::KeychainError(format!("Trust file is corrupted: {}", e)))?;
+        if file.version > TRUST_VERSION {
+            return Err(Error::KeychainError(format!(
+                "Trust file version {} is newer than this keychain-cli supports ({})",
+                file.version, TRUST_VERSION
+            )));
+        }
+        Ok(file)
+    }
+
+    fn save(&self, file: &TrustFile) -> Result<()> {
+        write_private(&self.path, serde_json::to_string_pretty(file)?.as_bytes())
+    }
+}
+
+impl Default for TrustStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn pin(hash: &str, service: &str) -> Pin {
+    Pin {
+        sha256: hash.to_string(),
+        service: service.to_string(),
+    }
+}
+
+fn key(manifest: &Path) -> String {
+    manifest
+        .canonicalize()
+        .unwrap_or_else(|_| manifest.to_path_buf())
+        .display()
+        .to_string()
+}
@@ -0,0 +1,343 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+use super::index::KeyIndex;
+use super::storage::{write_private, FileLock};
+use super::SecretStore;
+use crate::error::{Error, Result};
+
+/// Environment variable consulted for the vault passphrase
+pub const PASSPHRASE_ENV: &str = "KEYCHAIN_VAULT_PASSPHRASE";
+
+const VAULT_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+
+/// On-disk vault layout: KDF parameters and one encrypted JSON blob
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    kdf: KdfParams,
+    cipher: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(super) struct KdfParams {
+    algorithm: String,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    salt: String,
+}
+
+impl KdfParams {
+    pub(super) fn generate(m_cost: u32, t_cost: u32, p_cost: u32) -> Self {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        KdfParams {
+            algorithm: "argon2id".to_string(),
+            m_cost,
+            t_cost,
+            p_cost,
+            salt: BASE64.encode(salt),
+        }
+    }
+
+    /// Derive the 256-bit vault key from a passphrase
+    pub(super) fn derive_key(&self, passphrase: &str) -> Result<Zeroizing<[u8; 32]>> {
+        if self.algorithm != "argon2id" {
+            return Err(Error::KeychainError(format!(
+                "Unsupported vault KDF: {}",
+                self.algorithm
+            )));
+        }
+
+        let salt = decode_field(&self.salt, "salt")?;
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32))
+            .map_err(|e| Error::KeychainError(format!("Invalid KDF parameters: {}", e)))?;
+
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &salt, key.as_mut())
+            .map_err(|e| Error::KeychainError(format!("Key derivation failed: {}", e)))?;
+        Ok(key)
+    }
+}
+
+/// Passphrase-encrypted file backend for machines without a macOS Keychain
+///
+/// All secrets for a service live in `~/.keychain/<service>.vault`, sealed
+/// with XChaCha20-Poly1305 under an Argon2id-derived key.
+pub struct FileVault {
+    service_name: String,
+    path: PathBuf,
+    kdf: KdfParams,
+    key: Zeroizing<[u8; 32]>,
+    index: KeyIndex,
+}
+
+impl FileVault {
+    /// Open (or prepare to create) the vault for a service
+    pub fn open(service_name: &str, passphrase_fd: Option<i32>) -> Result<Self> {
+        let path = super::keychain_dir().join(format!("{}.vault", service_name));
+        let passphrase = read_passphrase(passphrase_fd, !path.exists())?;
+        // OWASP-recommended Argon2id baseline: 19 MiB, 2 iterations
+        Self::with_path(service_name, path, &passphrase, (19 * 1024, 2, 1))
+    }
+
+    /// Open a vault at an explicit path; `cost` is only used for new vaults
+    fn with_path(
+        service_name: &str,
+        path: PathBuf,
+        passphrase: &str,
+        cost: (u32, u32, u32),
+    ) -> Result<Self> {
+        let kdf = if path.exists() {
+            read_vault_file(&path)?.kdf
+        } else {
+            KdfParams::generate(cost.0, cost.1, cost.2)
+        };
+        let key = kdf.derive_key(passphrase)?;
+
+        let vault = FileVault {
+            service_name: service_name.to_string(),
+            path,
+            kdf,
+            key,
+            index: KeyIndex::new(service_name),
+        };
+
+        // Fail early on a wrong passphrase rather than on first use
+        vault.load()?;
+        Ok(vault)
+    }
+
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new(self.key.as_ref().into())
+    }
+
+    /// Authenticated data binding the ciphertext to this service
+    fn aad(&self) -> Vec<u8> {
+        format!("keychain-cli:v{}:{}", VAULT_VERSION, self.service_name).into_bytes()
+    }
+
+    /// Decrypt the vault contents
+    fn load(&self) -> Result<BTreeMap<String, String>> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        let file = read_vault_file(&self.path)?;
+        if file.version != VAULT_VERSION {
+            return Err(Error::KeychainError(format!(
+                "Unsupported vault version: {}",
+                file.version
+            )));
+        }
+
+        let nonce = decode_field(&file.nonce, "nonce")?;
+        let ciphertext = decode_field(&file.ciphertext, "ciphertext")?;
+        if nonce.len() != 24 {
+            return Err(Error::KeychainError("Vault nonce is corrupted".to_string()));
+        }
+
+        let plaintext = Zeroizing::new(
+            self.cipher()
+                .decrypt(
+                    XNonce::from_slice(&nonce),
+                    Payload { msg: &ciphertext, aad: &self.aad() },
+                )
+                .map_err(|_| {
+                    Error::KeychainError(
+                        "Failed to decrypt vault (wrong passphrase or corrupted file)".to_string(),
+                    )
+                })?,
+        );
+
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    /// Encrypt and write the vault contents with a fresh nonce
+    fn save(&self, secrets: &BTreeMap<String, String>) -> Result<()> {
+        let plaintext = Zeroizing::new(serde_json::to_vec(secrets)?);
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher()
+            .encrypt(&nonce, Payload { msg: &plaintext, aad: &self.aad() })
+            .map_err(|_| Error::KeychainError("Failed to encrypt vault".to_string()))?;
+
+        let file = VaultFile {
+            version: VAULT_VERSION,
+            kdf: self.kdf.clone(),
+            cipher: "xchacha20poly1305".to_string(),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        };
+
+        write_private(&self.path, serde_json::to_string_pretty(&file)?.as_bytes())
+    }
+}
+
+impl SecretStore for FileVault {
+    fn service_name(&self) -> &str {
+        &self.service_name
+    }
+
+    fn store(&self, key: &str, value: &str) -> Result<()> {
+        debug!("Storing {} in vault {}", key, self.path.display());
+
+        let _lock = FileLock::acquire(&self.path)?;
+        let mut secrets = self.load()?;
+        secrets.insert(key.to_string(), value.to_string());
+        self.save(&secrets)?;
+
+        self.index.save_key(key);
+        Ok(())
+    }
+
+    fn retrieve(&self, key: &str) -> Result<String> {
+        self.load()?
+            .remove(key)
+            .ok_or_else(|| Error::KeychainError(format!("Secret not found: {}", key)))
+    }
+
+    fn retrieve_all(&self) -> Result<Vec<(String, String)>> {
+        Ok(self.load()?.into_iter().collect())
+    }
+
+    fn list_stored(&self) -> Result<Vec<String>> {
+        Ok(self.load()?.into_keys().collect())
+    }
+
+    fn try_exists(&self, key: &str) -> Result<bool> {
+        Ok(self.load()?.contains_key(key))
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        let _lock = FileLock::acquire(&self.path)?;
+        let mut secrets = self.load()?;
+        if secrets.remove(key).is_none() {
+            return Err(Error::KeychainError(format!("Secret not found: {}", key)));
+        }
+        self.save(&secrets)?;
+
+        self.index.remove(key)?;
+        Ok(())
+    }
+}
+
+fn read_vault_file(path: &Path) -> Result<VaultFile> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| Error::KeychainError(format!("Failed to read vault: {}", e)))?;
+    serde_json::from_str(&content)
+        .map_err(|e| Error::KeychainError(format!("Vault file is corrupted: {}", e)))
+}
+
+pub(super) fn decode_field(value: &str, name: &str) -> Result<Vec<u8>> {
+    BASE64
+        .decode(value)
+        .map_err(|e| Error::KeychainError(format!("Invalid vault {}: {}", name, e)))
+}
+
+/// Resolve the vault passphrase: file descriptor, then env var, then prompt
+fn read_passphrase(fd: Option<i32>, new_vault: bool) -> Result<Zeroizing<String>> {
+    if let Some(fd) = fd {
+        return read_passphrase_fd(fd);
+    }
+
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(Zeroizing::new(passphrase));
+    }
+
+    let mut prompt = dialoguer::Password::new().with_prompt("Vault passphrase");
+    if new_vault {
+        prompt = prompt.with_confirmation("Confirm passphrase", "Passphrases don't match");
+    }
+    prompt
+        .interact()
+        .map(Zeroizing::new)
+        .map_err(|e| Error::KeychainError(format!("Failed to read passphrase: {}", e)))
+}
+
+#[cfg(unix)]
+fn read_passphrase_fd(fd: i32) -> Result<Zeroizing<String>> {
+    use std::os::unix::io::FromRawFd;
+
+    // SAFETY: the caller hands us ownership of this descriptor via --passphrase-fd
+    let mut file = unsafe { fs::File::from_raw_fd(fd) };
+    let mut passphrase = Zeroizing::new(String::new());
+    file.read_to_string(&mut passphrase)
+        .map_err(|e| Error::KeychainError(format!("Failed to read passphrase from fd {}: {}", fd, e)))?;
+
+    let trimmed = passphrase.trim_end_matches(['\n', '\r']).len();
+    passphrase.truncate(trimmed);
+    Ok(passphrase)
+}
+
+#[cfg(not(unix))]
+fn read_passphrase_fd(_fd: i32) -> Result<Zeroizing<String>> {
+    Err(Error::ConfigError(
+        "--passphrase-fd is only supported on Unix".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_COST: (u32, u32, u32) = (1024, 1, 1);
+
+    fn temp_vault_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("keychain-cli-vault-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{}.vault", name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path = temp_vault_path("round_trip");
+        let vault = FileVault::with_path("svc", path.clone(), "hunter2", TEST_COST).unwrap();
+
+        let mut secrets = BTreeMap::new();
+        secrets.insert("API_TOKEN".to_string(), "sk-test-123".to_string());
+        vault.save(&secrets).unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("sk-test-123"));
+
+        let reopened = FileVault::with_path("svc", path, "hunter2", TEST_COST).unwrap();
+        assert_eq!(reopened.retrieve("API_TOKEN").unwrap(), "sk-test-123");
+    }
+
+    #[test]
+    fn test_wrong_passphrase() {
+        let path = temp_vault_path("wrong_passphrase");
+        let vault = FileVault::with_path("svc", path.clone(), "correct", TEST_COST).unwrap();
+        vault.save(&BTreeMap::new()).unwrap();
+
+        assert!(FileVault::with_path("svc", path, "incorrect", TEST_COST).is_err());
+    }
+
+    #[test]
+    fn test_bound_to_service() {
+        let path = temp_vault_path("bound_to_service");
+        let vault = FileVault::with_path("svc-a", path.clone(), "pass", TEST_COST).unwrap();
+        vault.save(&BTreeMap::new()).unwrap();
+
+        assert!(FileVault::with_path("svc-b", path, "pass", TEST_COST).is_err());
+    }
+}
@@ -5,10 +5,17 @@ use std::path::PathBuf;
 mod commands;
 mod config;
 mod error;
+mod import;
 mod keychain;
+mod mask;
+mod output;
+mod project;
 mod rules;
 
-use commands::{check, load, setup, validate};
+use commands::{
+    allow, check, delete, deny, hook, inject, list, load, reconcile, restore, run, set, setup,
+    validate,
+};
 
 #[derive(Parser)]
 #[command(name = "keychain-cli")]
@@ -29,7 +36,8 @@ FEATURES:
   • <1 second load time for 61+ secrets
   • Hook-based command validation blocks dangerous operations
   • Dynamic rule system (no recompilation needed)
-  • Multiple output formats (bash, json, export)
+  • Output formats for bash/zsh, fish, PowerShell, nushell, tcsh, JSON,
+    dotenv, Docker, systemd and Kubernetes Secrets
 
 EXAMPLES:
   # Verify configuration
@@ -55,6 +63,14 @@ struct Cli {
     #[arg(default_value = "claude-dev")]
     service_name: String,
 
+    #[arg(global = true, long, help = "Secret storage backend: keychain, file, mock or mock:<path>")]
+    #[arg(default_value = "keychain")]
+    backend: String,
+
+    #[arg(global = true, long, value_name = "FD")]
+    #[arg(help = "Read the file vault passphrase from this file descriptor")]
+    passphrase_fd: Option<i32>,
+
     #[arg(global = true, long, help = "Enable verbose logging")]
     verbose: bool,
 }
@@ -64,27 +80,232 @@ enum Commands {
     /// Setup: Store .env secrets into Keychain
     Setup {
         /// Path to .env file
+        #[arg(short, long, required_unless_present = "stdin", conflicts_with = "stdin")]
+        env_file: Option<PathBuf>,
+
+        /// Read dotenv text from stdin instead of a file
+        #[arg(long)]
+        stdin: bool,
+
+        /// Only setup these keys (comma-separated; globs, /regex/, tag:NAME and !TERM work too)
         #[arg(short, long)]
-        env_file: PathBuf,
+        keys: Option<String>,
+
+        /// Skip interactive selection and confirmation
+        #[arg(long)]
+        force: bool,
+
+        /// Store every variable in the file, not only the sensitive ones
+        #[arg(long)]
+        all: bool,
+
+        /// Show what would be stored, changed or left alone, then exit
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Tags to record for the stored keys (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        tags: Vec<String>,
+
+        /// Expiry to record for the stored keys (YYYY-MM-DD or RFC 3339)
+        #[arg(long, value_parser = setup::parse_expiry)]
+        expires: Option<chrono::DateTime<chrono::Utc>>,
+
+        /// Expand ${VAR} references in unquoted and double-quoted values
+        #[arg(long)]
+        interpolate: bool,
+
+        /// After storing, replace the values in the .env with keychain:// references
+        #[arg(long, conflicts_with_all = ["stdin", "dry_run"])]
+        scrub: bool,
 
-        /// Only setup specific keys (comma-separated)
+        /// Keep the original .env as an encrypted backup at this path when scrubbing
+        #[arg(long, value_name = "PATH", requires = "scrub")]
+        scrub_backup: Option<PathBuf>,
+    },
+
+    /// Set: Store a single value from a hidden prompt or stdin
+    Set {
+        /// Variable name
+        #[arg(value_name = "KEY")]
+        key: String,
+
+        /// Overwrite an existing value without asking
+        #[arg(long)]
+        force: bool,
+
+        /// Tags to record for the key (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        tags: Vec<String>,
+
+        /// Expiry to record for the key (YYYY-MM-DD or RFC 3339)
+        #[arg(long, value_parser = setup::parse_expiry)]
+        expires: Option<chrono::DateTime<chrono::Utc>>,
+    },
+
+    /// Import: Store secrets from JSON, YAML, TOML, compose or Kubernetes files
+    Import {
+        /// File to import
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Source format: json, yaml, toml, compose, k8s, bitwarden,
+        /// 1password-csv, 1pux, keepass-xml, keepass-csv or pass (a directory)
+        #[arg(long = "from", value_name = "FORMAT")]
+        from: import::Format,
+
+        /// Joins nested key segments (db.password -> DB_PASSWORD)
+        #[arg(long, default_value = "_")]
+        separator: String,
+
+        /// Case of flattened key names: upper, lower or preserve
+        #[arg(long, default_value = "upper")]
+        key_case: import::KeyCase,
+
+        /// Key name for password manager entries ({folder}, {title}, {field})
+        #[arg(long, default_value = "{title}_{field}")]
+        key_template: String,
+
+        /// Password manager fields to import (comma-separated, * and ? allowed;
+        /// default: password and custom fields)
+        #[arg(long, value_delimiter = ',')]
+        fields: Option<Vec<String>>,
+
+        /// Only import these keys, after flattening (same selectors as setup --keys)
         #[arg(short, long)]
         keys: Option<String>,
 
-        /// Skip interactive confirmation
+        /// Skip interactive selection and confirmation
+        #[arg(long)]
+        force: bool,
+
+        /// Store every imported variable, not only the sensitive ones
+        #[arg(long)]
+        all: bool,
+
+        /// Show what would be stored, changed or left alone, then exit
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Tags to record for the stored keys (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        tags: Vec<String>,
+
+        /// Expiry to record for the stored keys (YYYY-MM-DD or RFC 3339)
+        #[arg(long, value_parser = setup::parse_expiry)]
+        expires: Option<chrono::DateTime<chrono::Utc>>,
+
+        /// Expand ${VAR} references in compose env_file values
+        #[arg(long)]
+        interpolate: bool,
+    },
+
+    /// Restore: Decrypt a .env backup written by setup --scrub-backup
+    Restore {
+        /// Backup file
+        #[arg(value_name = "BACKUP")]
+        backup: PathBuf,
+
+        /// Write the restored file here (mode 0600) instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Replace an existing output file
         #[arg(long)]
         force: bool,
     },
 
     /// Load: Retrieve secrets from Keychain
     Load {
-        /// Output format: bash, json, or export
+        /// Output format: bash (or export), json, fish, powershell, nushell, tcsh, auto,
+        /// dotenv, docker, systemd, or k8s-secret
         #[arg(short, long, default_value = "bash")]
         format: String,
 
-        /// Only load specific keys (comma-separated)
+        /// Only load these keys: comma-separated names, globs (AWS_*), /regex/,
+        /// tag:NAME, and !TERM to exclude
         #[arg(short, long)]
         keys: Option<String>,
+
+        /// Fail, listing them, if any --keys term matches no stored key
+        #[arg(long)]
+        strict: bool,
+
+        /// Expose stored key OLD as NEW (repeatable; OLD may be mapped more than once)
+        #[arg(long, value_name = "OLD=NEW")]
+        map: Vec<String>,
+
+        /// File of OLD=NEW lines, added to --map
+        #[arg(long, value_name = "PATH")]
+        map_file: Option<PathBuf>,
+
+        /// Remove this prefix from key names that have it (not applied to --map keys)
+        #[arg(long)]
+        strip_prefix: Option<String>,
+
+        /// Add this prefix to key names, after --strip-prefix (not applied to --map keys)
+        #[arg(long)]
+        prefix: Option<String>,
+
+        /// Secret name for k8s-secret (default: the service name)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Secret namespace for k8s-secret
+        #[arg(long)]
+        namespace: Option<String>,
+    },
+
+    /// Inject: Render a template, replacing keychain:// and {{ keychain "KEY" }} references
+    #[command(visible_alias = "resolve")]
+    Inject {
+        /// Template file (dotenv, YAML, JSON or any text); - reads stdin
+        #[arg(value_name = "TEMPLATE")]
+        template: PathBuf,
+
+        /// Write the result here (mode 0600) instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Replace an existing output file
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Run: Start a command with secrets in its environment only
+    Run {
+        /// Only pass these keys: comma-separated names, globs (AWS_*), /regex/,
+        /// tag:NAME, and !TERM to exclude
+        #[arg(short, long)]
+        keys: Option<String>,
+
+        /// Fail, listing them, if any --keys term matches no stored key
+        #[arg(long)]
+        strict: bool,
+
+        /// Expose stored key OLD as NEW (repeatable; OLD may be mapped more than once)
+        #[arg(long, value_name = "OLD=NEW")]
+        map: Vec<String>,
+
+        /// File of OLD=NEW lines, added to --map
+        #[arg(long, value_name = "PATH")]
+        map_file: Option<PathBuf>,
+
+        /// Remove this prefix from key names that have it (not applied to --map keys)
+        #[arg(long)]
+        strip_prefix: Option<String>,
+
+        /// Add this prefix to key names, after --strip-prefix (not applied to --map keys)
+        #[arg(long)]
+        prefix: Option<String>,
+
+        /// Replace secret values in the command's output with ***KEY***
+        #[arg(long)]
+        mask: bool,
+
+        /// Command and arguments, after --
+        #[arg(last = true, required = true, value_name = "COMMAND")]
+        command: Vec<String>,
     },
 
     /// Validate: Check if command violates security rules (for Hook)
@@ -100,37 +321,283 @@ enum Commands {
         #[arg(short, long)]
         verbose: bool,
     },
+
+    /// List: Show indexed key names and metadata (never values)
+    List {
+        /// Output format: plain, table, or json
+        #[arg(short, long, default_value = "plain")]
+        format: String,
+
+        /// Shorthand for --format json
+        #[arg(long, conflicts_with = "format")]
+        json: bool,
+    },
+
+    /// Reconcile: Find index entries missing from the store and vice versa
+    Reconcile {
+        /// Prune ghost entries and adopt orphaned secrets into the index
+        #[arg(long)]
+        fix: bool,
+    },
+
+    /// Delete: Remove secrets and their index entries
+    #[command(visible_alias = "rm")]
+    Delete {
+        /// Keys to delete (comma-separated)
+        #[arg(value_name = "KEY[,KEY...]", required_unless_present = "all")]
+        keys: Option<String>,
+
+        /// Delete every key stored for the service
+        #[arg(long, conflicts_with = "keys")]
+        all: bool,
+
+        /// Skip interactive confirmation
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Hook-shell: Print a prompt hook that loads .keychain.toml keys on cd
+    HookShell {
+        /// Shell to hook: bash, zsh or fish
+        #[arg(value_name = "SHELL")]
+        shell: String,
+    },
+
+    /// Hook-export: Commands the prompt hook evaluates (used by hook-shell)
+    #[command(hide = true)]
+    HookExport {
+        #[arg(value_name = "SHELL")]
+        shell: String,
+    },
+
+    /// Allow: Review a .keychain.toml and trust its current content
+    Allow {
+        /// Manifest, or a directory to search upwards from (default: current directory)
+        #[arg(value_name = "PATH")]
+        path: Option<PathBuf>,
+
+        /// Skip interactive confirmation
+        #[arg(long)]
+        yes: bool,
+    },
+
+    /// Deny: Stop trusting a .keychain.toml
+    Deny {
+        /// Manifest, or a directory to search upwards from (default: current directory)
+        #[arg(value_name = "PATH")]
+        path: Option<PathBuf>,
+    },
+}
+
+/// Open the secret store selected by the global flags
+fn open_store(cli: &Cli) -> error::Result<Box<dyn keychain::SecretStore>> {
+    keychain::open_backend(&cli.backend, &cli.service_name, cli.passphrase_fd)
+}
+
+/// Collect `--map`, `--map-file`, `--strip-prefix` and `--prefix`
+fn renames(
+    map: &[String],
+    map_file: Option<&std::path::Path>,
+    strip_prefix: &Option<String>,
+    prefix: &Option<String>,
+) -> error::Result<config::Renames> {
+    let mut renames = config::Renames {
+        strip_prefix: strip_prefix.clone(),
+        prefix: prefix.clone(),
+        ..Default::default()
+    };
+    if let Some(path) = map_file {
+        renames.add_file(&std::fs::read_to_string(path)?)?;
+    }
+    for pair in map {
+        renames.add(pair)?;
+    }
+    Ok(renames)
 }
 
 fn main() -> error::Result<()> {
     let cli = Cli::parse();
 
-    // Initialize logging
-    if cli.verbose {
-        std::env::set_var("RUST_LOG", "debug");
-    } else {
-        std::env::set_var("RUST_LOG", "info");
-    }
-    env_logger::init();
+    // Initialize logging (without touching RUST_LOG, which `run` children inherit)
+    // The prompt hook runs before every prompt, so it only speaks up for problems
+    let level = match cli.command {
+        _ if cli.verbose => "debug",
+        Commands::HookExport { .. } => "warn",
+        _ => "info",
+    };
+    env_logger::Builder::new().parse_filters(level).init();
 
-    info!("keychain-cli started with service: {}", cli.service_name);
+    info!("keychain-cli started with service: {} (backend: {})", cli.service_name, cli.backend);
 
-    match cli.command {
+    match &cli.command {
         Commands::Setup {
             env_file,
+            stdin: _,
             keys,
             force,
+            all,
+            dry_run,
+            tags,
+            expires,
+            interpolate,
+            scrub,
+            scrub_backup,
         } => {
-            setup::execute(&env_file, keys.as_deref(), force, &cli.service_name)?;
+            let store = open_store(&cli)?;
+            let options = setup::SetupOptions {
+                keys: keys.as_deref(),
+                force: *force,
+                all: *all,
+                dry_run: *dry_run,
+                tags: tags.clone(),
+                expires_at: *expires,
+                interpolate: *interpolate,
+                scrub: *scrub,
+                scrub_backup: scrub_backup.as_deref(),
+            };
+            setup::execute(env_file.as_deref(), &options, store.as_ref())?;
         }
-        Commands::Load { format, keys } => {
-            load::execute(&format, keys.as_deref(), &cli.service_name)?;
+        Commands::Set {
+            key,
+            force,
+            tags,
+            expires,
+        } => {
+            let store = open_store(&cli)?;
+            let options = setup::SetupOptions {
+                keys: None,
+                force: *force,
+                all: true,
+                dry_run: false,
+                tags: tags.clone(),
+                expires_at: *expires,
+                interpolate: false,
+                scrub: false,
+                scrub_backup: None,
+            };
+            set::execute(key, &options, store.as_ref())?;
+        }
+        Commands::Import {
+            file,
+            from,
+            separator,
+            key_case,
+            key_template,
+            fields,
+            keys,
+            force,
+            all,
+            dry_run,
+            tags,
+            expires,
+            interpolate,
+        } => {
+            let store = open_store(&cli)?;
+            let rule = import::KeyRule {
+                separator: separator.clone(),
+                case: *key_case,
+            };
+            let item_rule = import::ItemRule {
+                template: key_template.clone(),
+                fields: fields.clone(),
+            };
+            let options = setup::SetupOptions {
+                keys: keys.as_deref(),
+                force: *force,
+                all: *all,
+                dry_run: *dry_run,
+                tags: tags.clone(),
+                expires_at: *expires,
+                interpolate: *interpolate,
+                scrub: false,
+                scrub_backup: None,
+            };
+            commands::import::execute(file, *from, &rule, &item_rule, &options, store.as_ref())?;
+        }
+        Commands::Restore {
+            backup,
+            output,
+            force,
+        } => {
+            restore::execute(backup, output.as_deref(), *force)?;
+        }
+        Commands::Load {
+            format,
+            keys,
+            strict,
+            map,
+            map_file,
+            strip_prefix,
+            prefix,
+            name,
+            namespace,
+        } => {
+            let store = open_store(&cli)?;
+            let renames = renames(map, map_file.as_deref(), strip_prefix, prefix)?;
+            load::execute(
+                format,
+                keys.as_deref(),
+                *strict,
+                &renames,
+                name.as_deref(),
+                namespace.as_deref(),
+                store.as_ref(),
+            )?;
+        }
+        Commands::Inject {
+            template,
+            output,
+            force,
+        } => {
+            let store = open_store(&cli)?;
+            let open_service = |service: &str| keychain::open_backend(&cli.backend, service, None);
+            inject::execute(template, output.as_deref(), *force, store.as_ref(), &open_service)?;
+        }
+        Commands::Run {
+            keys,
+            strict,
+            map,
+            map_file,
+            strip_prefix,
+            prefix,
+            mask,
+            command,
+        } => {
+            let store = open_store(&cli)?;
+            let renames = renames(map, map_file.as_deref(), strip_prefix, prefix)?;
+            run::execute(command, keys.as_deref(), *strict, &renames, *mask, store.as_ref())?;
         }
         Commands::Validate { command } => {
-            validate::execute(command, &cli.service_name)?;
+            validate::execute(command.clone(), &cli.service_name)?;
         }
         Commands::Check { verbose } => {
-            check::execute(verbose, &cli.service_name)?;
+            let store = open_store(&cli)?;
+            check::execute(*verbose, store.as_ref())?;
+        }
+        Commands::List { format, json } => {
+            let store = open_store(&cli)?;
+            let format = if *json { "json" } else { format.as_str() };
+            list::execute(format, store.as_ref())?;
+        }
+        Commands::Reconcile { fix } => {
+            let store = open_store(&cli)?;
+            reconcile::execute(*fix, store.as_ref())?;
+        }
+        Commands::Delete { keys, all, force } => {
+            let store = open_store(&cli)?;
+            delete::execute(keys.as_deref(), *all, *force, store.as_ref())?;
+        }
+        Commands::HookShell { shell } => {
+            hook::shell(shell, &cli.backend, &cli.service_name)?;
+        }
+        Commands::HookExport { shell } => {
+            hook::export(shell, &cli.backend, &cli.service_name)?;
+        }
+        Commands::Allow { path, yes } => {
+            allow::execute(path.as_deref(), *yes, &cli.service_name)?;
+        }
+        Commands::Deny { path } => {
+            deny::execute(path.as_deref())?;
         }
     }
 
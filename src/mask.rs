@@ -0,0 +1,156 @@
+use zeroize::Zeroizing;
+
+/// Values shorter than this are not masked; they match ordinary output
+pub const MIN_VALUE_LEN: usize = 4;
+
+/// Streaming replacement of secret values with `***KEY***`
+///
+/// Output is fed in arbitrary chunks. Bytes are passed on as soon as they
+/// can't be the start of a value; only a tail that is a prefix of some value
+/// is held back until the next chunk (or `flush`) decides it, so a value
+/// split across reads is still caught without buffering whole lines.
+#[derive(Clone)]
+pub struct Masker {
+    /// (value, replacement), longest value first
+    patterns: Vec<(Zeroizing<Vec<u8>>, Vec<u8>)>,
+    /// Pattern indices by first byte
+    by_first: Vec<Vec<usize>>,
+    longest: usize,
+    pending: Zeroizing<Vec<u8>>,
+}
+
+impl Masker {
+    /// Build from `(key, value)` pairs; when two keys share a value the
+    /// first one names the replacement
+    pub fn new<'a>(secrets: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let mut patterns: Vec<(Zeroizing<Vec<u8>>, Vec<u8>)> = Vec::new();
+        for (key, value) in secrets {
+            let duplicate = patterns.iter().any(|(v, _)| v.as_slice() == value.as_bytes());
+            if value.len() < MIN_VALUE_LEN || duplicate {
+                continue;
+            }
+            patterns.push((
+                Zeroizing::new(value.as_bytes().to_vec()),
+                format!("***{}***", key).into_bytes(),
+            ));
+        }
+        // Stable, so equal lengths keep their given order
+        patterns.sort_by_key(|(value, _)| std::cmp::Reverse(value.len()));
+
+        let mut by_first = vec![Vec::new(); 256];
+        for (i, (value, _)) in patterns.iter().enumerate() {
+            by_first[value[0] as usize].push(i);
+        }
+        let longest = patterns.first().map_or(0, |(value, _)| value.len());
+
+        Masker {
+            patterns,
+            by_first,
+            longest,
+            pending: Zeroizing::new(Vec::new()),
+        }
+    }
+
+    /// Whether there is nothing to mask
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Whether bytes are being held back waiting for more input
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Mask `chunk`, returning everything that can be decided so far
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<u8> {
+        self.pending.extend_from_slice(chunk);
+        self.process(false)
+    }
+
+    /// Mask and return whatever is held back, treating it as final
+    pub fn flush(&mut self) -> Vec<u8> {
+        self.process(true)
+    }
+
+    fn process(&mut self, last: bool) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.pending.len());
+        let mut pos = 0;
+
+        while pos < self.pending.len() {
+            let rest = &self.pending[pos..];
+            let candidates = &self.by_first[rest[0] as usize];
+
+            // A longer value might still complete with the next chunk
+            if !last
+                && rest.len() < self.longest
+                && candidates.iter().any(|&i| {
+                    let value = &self.patterns[i].0;
+                    value.len() > rest.len() && value.starts_with(rest)
+                })
+            {
+                break;
+            }
+
+            match candidates.iter().find(|&&i| rest.starts_with(&self.patterns[i].0)) {
+                Some(&i) => {
+                    out.extend_from_slice(&self.patterns[i].1);
+                    pos += self.patterns[i].0.len();
+                }
+                None => {
+                    out.push(rest[0]);
+                    pos += 1;
+                }
+            }
+        }
+
+        self.pending.drain(..pos);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mask_in_chunks(masker: &mut Masker, input: &[u8], size: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        for chunk in input.chunks(size) {
+            out.extend(masker.feed(chunk));
+        }
+        out.extend(masker.flush());
+        out
+    }
+
+    #[test]
+    fn test_masks_values_split_across_chunks() {
+        let input = b"token=sk-live-123 pw=hunter22 again sk-live-123\n";
+        let expected = b"token=***TOKEN*** pw=***PW*** again ***TOKEN***\n";
+
+        for size in 1..=input.len() {
+            let mut masker = Masker::new([("TOKEN", "sk-live-123"), ("PW", "hunter22")]);
+            assert_eq!(mask_in_chunks(&mut masker, input, size), expected, "chunk size {}", size);
+        }
+    }
+
+    #[test]
+    fn test_passes_output_through_without_delay() {
+        let mut masker = Masker::new([("TOKEN", "sk-live-123")]);
+
+        assert_eq!(masker.feed(b"Password: "), b"Password: ");
+        assert!(!masker.has_pending());
+
+        assert_eq!(masker.feed(b"x sk-li"), b"x ");
+        assert!(masker.has_pending());
+        assert_eq!(masker.feed(b"ve"), b"");
+        assert_eq!(masker.flush(), b"sk-live", "a prefix alone is not the value");
+    }
+
+    #[test]
+    fn test_prefers_longest_value_and_skips_short_ones() {
+        let mut masker = Masker::new([("SHORT", "abcd"), ("LONG", "abcdef"), ("PORT", "80")]);
+        assert_eq!(
+            mask_in_chunks(&mut masker, b"abcdef abcdx :80", 3),
+            b"***LONG*** ***SHORT***x :80"
+        );
+    }
+}
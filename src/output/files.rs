@@ -0,0 +1,174 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_yaml::{Mapping, Value};
+
+use crate::error::{Error, Result};
+
+/// `KEY=value` that `setup` reads back unchanged
+///
+/// Values made only of unremarkable characters stay bare; anything else is
+/// double-quoted with `\\`, `\"`, `\$`, `\n`, `\r` and `\t` escaped, so
+/// interpolation and inline comments can't change it on the way back in.
+pub fn dotenv(key: &str, value: &str) -> String {
+    let bare = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-.:/@+=,%".contains(c));
+    if bare {
+        return format!("{}={}\n", key, value);
+    }
+
+    let mut escaped = String::with_capacity(value.len() + 2);
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '$' => escaped.push_str("\\$"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c => escaped.push(c),
+        }
+    }
+    format!("{}=\"{}\"\n", key, escaped)
+}
+
+/// `KEY=value` for `docker run --env-file`
+///
+/// Docker takes everything after `=` literally, quotes included, and has no
+/// way to continue a line, so values with line breaks are refused.
+pub fn docker(key: &str, value: &str) -> Result<String> {
+    if value.contains(['\n', '\r']) {
+        return Err(Error::ValidationError(format!(
+            "{} contains a line break, which a Docker env-file can't hold",
+            key
+        )));
+    }
+    Ok(format!("{}={}\n", key, value))
+}
+
+/// `KEY="value"` for a systemd `EnvironmentFile=`
+///
+/// Inside double quotes systemd un-escapes `\"`, `\\`, `` \` `` and `\$`
+/// and keeps newlines, so those four are escaped and newlines written as is.
+pub fn systemd(key: &str, value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | '`' | '$') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    format!("{}=\"{}\"\n", key, escaped)
+}
+
+/// An `Opaque` Kubernetes `Secret` manifest with base64 `data`
+pub fn k8s_secret(
+    secrets: &[(String, String)],
+    name: &str,
+    namespace: Option<&str>,
+) -> Result<String> {
+    let labels = std::iter::once(("name", name)).chain(namespace.map(|n| ("namespace", n)));
+    for (label, value) in labels {
+        if !is_dns_subdomain(value) {
+            return Err(Error::ValidationError(format!(
+                "Invalid Secret {} '{}': use lowercase letters, digits, '-' and '.'",
+                label, value
+            )));
+        }
+    }
+
+    let mut metadata = Mapping::new();
+    metadata.insert("name".into(), name.into());
+    if let Some(namespace) = namespace {
+        metadata.insert("namespace".into(), namespace.into());
+    }
+
+    let mut data = Mapping::new();
+    for (key, value) in secrets {
+        data.insert(key.as_str().into(), STANDARD.encode(value).into());
+    }
+
+    let mut manifest = Mapping::new();
+    manifest.insert("apiVersion".into(), "v1".into());
+    manifest.insert("kind".into(), "Secret".into());
+    manifest.insert("metadata".into(), Value::Mapping(metadata));
+    manifest.insert("type".into(), "Opaque".into());
+    manifest.insert("data".into(), Value::Mapping(data));
+
+    serde_yaml::to_string(&manifest)
+        .map_err(|e| Error::ValidationError(format!("Failed to write manifest: {}", e)))
+}
+
+/// Kubernetes object name rules (RFC 1123 subdomain)
+fn is_dns_subdomain(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 253
+        && name.split('.').all(|label| {
+            !label.is_empty()
+                && label.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::dotenv::{self as parser, ParseOptions};
+    use crate::import::{k8s, KeyCase, KeyRule};
+
+    fn values() -> Vec<(String, String)> {
+        [
+            ("PLAIN", "postgres://db:5432/app"),
+            ("QUOTES", "it's \"quoted\""),
+            ("DOLLAR", "$HOME ${USER} `id` \\$x"),
+            ("COMMENT", "value # not a comment"),
+            ("NEWLINES", "line1\nline2\r\n"),
+            ("UNICODE", "héllo ✓ 🔑\ttab "),
+            ("EMPTY", ""),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn test_dotenv_round_trips_through_the_parser() {
+        let text: String = values().iter().map(|(k, v)| dotenv(k, v)).collect();
+
+        for interpolate in [false, true] {
+            let parsed: Vec<(String, String)> = parser::parse(&text, ParseOptions { interpolate })
+                .unwrap()
+                .into_iter()
+                .map(|e| (e.key, e.value))
+                .collect();
+            assert_eq!(parsed, values(), "interpolate: {}", interpolate);
+        }
+        assert!(text.starts_with("PLAIN=postgres://db:5432/app\n"));
+    }
+
+    #[test]
+    fn test_k8s_secret_round_trips_through_the_importer() {
+        let manifest = k8s_secret(&values(), "app-secrets", Some("prod")).unwrap();
+        let rule = KeyRule {
+            separator: "_".to_string(),
+            case: KeyCase::Preserve,
+        };
+
+        let mut parsed = k8s::parse(&manifest, &rule).unwrap();
+        let mut expected = values();
+        parsed.sort();
+        expected.sort();
+        assert_eq!(parsed, expected);
+        assert!(manifest.contains("  name: app-secrets\n  namespace: prod\n"), "{}", manifest);
+        assert!(k8s_secret(&values(), "App_Secrets", None).is_err());
+        assert!(k8s_secret(&values(), "app", Some("-prod")).is_err());
+    }
+
+    #[test]
+    fn test_docker_refuses_line_breaks() {
+        assert_eq!(docker("A", "it's \"raw\" $x").unwrap(), "A=it's \"raw\" $x\n");
+        assert!(docker("B", "line1\nline2").is_err());
+    }
+}
@@ -0,0 +1,256 @@
+use serde_json::json;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+
+pub mod files;
+pub mod shell;
+
+/// Output formats understood by `load --format`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// `export KEY='value'` for sh, bash, zsh and ksh (also `export`)
+    Bash,
+    Json,
+    Fish,
+    PowerShell,
+    /// A NUON record for `from nuon | load-env`
+    Nushell,
+    /// `setenv` for tcsh and csh
+    Tcsh,
+    /// `KEY=value` that `setup` can read back
+    Dotenv,
+    /// Unquoted `KEY=value` for `docker run --env-file`
+    Docker,
+    /// systemd `EnvironmentFile=`
+    Systemd,
+    /// Kubernetes `Secret` manifest
+    K8sSecret,
+}
+
+/// Settings only some formats use
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// `metadata.name` of a `k8s-secret`
+    pub name: String,
+    /// `metadata.namespace` of a `k8s-secret`; omitted when `None`
+    pub namespace: Option<String>,
+}
+
+impl Format {
+    /// The format for the shell that started us
+    ///
+    /// Looks at the parent process first, since that's the shell evaluating
+    /// our output, then at `$SHELL`, and falls back to `bash`.
+    pub fn detect() -> Format {
+        let detected = parent_process_name()
+            .and_then(|name| Format::for_shell(&name))
+            .or_else(|| std::env::var("SHELL").ok().and_then(|shell| Format::for_shell(&shell)));
+
+        match detected {
+            Some(format) => format,
+            None => {
+                log::warn!("Could not detect the shell; using bash syntax");
+                Format::Bash
+            }
+        }
+    }
+
+    /// Whether the output sets variables, so names must be valid ones
+    pub fn sets_variables(self) -> bool {
+        !matches!(self, Format::Json | Format::K8sSecret)
+    }
+
+    /// Format for a shell given by name or path (`-zsh`, `/usr/bin/fish`,
+    /// `pwsh.exe`)
+    pub fn for_shell(name: &str) -> Option<Format> {
+        let name = Path::new(name.trim()).file_name()?.to_str()?;
+        let name = name.trim_start_matches('-');
+        let name = name.strip_suffix(".exe").unwrap_or(name);
+
+        match name.to_ascii_lowercase().as_str() {
+            "sh" | "bash" | "zsh" | "ksh" | "mksh" | "dash" | "ash" => Some(Format::Bash),
+            "fish" => Some(Format::Fish),
+            "pwsh" | "powershell" => Some(Format::PowerShell),
+            "nu" | "nushell" => Some(Format::Nushell),
+            "tcsh" | "csh" => Some(Format::Tcsh),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "bash" | "sh" | "zsh" | "export" => Ok(Format::Bash),
+            "json" => Ok(Format::Json),
+            "fish" => Ok(Format::Fish),
+            "powershell" | "pwsh" => Ok(Format::PowerShell),
+            "nushell" | "nu" => Ok(Format::Nushell),
+            "tcsh" | "csh" => Ok(Format::Tcsh),
+            "dotenv" | "env" => Ok(Format::Dotenv),
+            "docker" => Ok(Format::Docker),
+            "systemd" => Ok(Format::Systemd),
+            "k8s-secret" | "kubernetes-secret" => Ok(Format::K8sSecret),
+            "auto" => Ok(Format::detect()),
+            _ => Err(Error::ValidationError(format!(
+                "Unknown format: {}. Use bash, export, json, fish, powershell, nushell, \
+                 tcsh, auto, dotenv, docker, systemd or k8s-secret",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Format::Bash => "bash",
+            Format::Json => "json",
+            Format::Fish => "fish",
+            Format::PowerShell => "powershell",
+            Format::Nushell => "nushell",
+            Format::Tcsh => "tcsh",
+            Format::Dotenv => "dotenv",
+            Format::Docker => "docker",
+            Format::Systemd => "systemd",
+            Format::K8sSecret => "k8s-secret",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Render `secrets` in `format`, in the given order
+pub fn render(format: Format, secrets: &[(String, String)], options: &Options) -> Result<String> {
+    let line = |emit: fn(&str, &str) -> String| {
+        secrets.iter().map(|(key, value)| emit(key, value)).collect::<String>()
+    };
+
+    Ok(match format {
+        Format::Bash => line(shell::posix),
+        Format::Fish => line(shell::fish),
+        Format::PowerShell => line(shell::powershell),
+        Format::Tcsh => line(shell::tcsh),
+        Format::Nushell => shell::nushell(secrets),
+        Format::Dotenv => line(files::dotenv),
+        Format::Systemd => line(files::systemd),
+        Format::Docker => secrets
+            .iter()
+            .map(|(key, value)| files::docker(key, value))
+            .collect::<Result<String>>()?,
+        Format::K8sSecret => {
+            files::k8s_secret(secrets, &options.name, options.namespace.as_deref())?
+        }
+        Format::Json => {
+            let mut obj = serde_json::Map::new();
+            for (key, value) in secrets {
+                obj.insert(key.clone(), json!(value));
+            }
+            serde_json::to_string_pretty(&serde_json::Value::Object(obj))? + "\n"
+        }
+    })
+}
+
+#[cfg(unix)]
+fn parent_process_name() -> Option<String> {
+    let ppid = std::os::unix::process::parent_id();
+    if let Ok(comm) = std::fs::read_to_string(format!("/proc/{}/comm", ppid)) {
+        return Some(comm.trim().to_string());
+    }
+    let output = std::process::Command::new("ps")
+        .args(["-o", "comm=", "-p", &ppid.to_string()])
+        .output()
+        .ok()?;
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !name.is_empty()).then_some(name)
+}
+
+#[cfg(not(unix))]
+fn parent_process_name() -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Values that break naive quoting in at least one shell
+    fn adversarial() -> Vec<(String, String)> {
+        [
+            ("PLAIN", "simple"),
+            ("QUOTES", "it's \"quoted\" ‘smart’"),
+            ("DOLLAR", "$HOME ${USER} $(id) %PATH%"),
+            ("BACKTICK", "`id` and `whoami`"),
+            ("BACKSLASH", "C:\\path\\n \\"),
+            ("NEWLINES", "line1\nline2\r\nline3"),
+            ("BANG", "!! history !$"),
+            ("UNICODE", "héllo ✓ 🔑\ttab"),
+            ("EMPTY", ""),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+    }
+
+    fn options() -> Options {
+        Options {
+            name: "app-secrets".to_string(),
+            namespace: Some("prod".to_string()),
+        }
+    }
+
+    fn golden(format: Format) -> String {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/output")
+            .join(format!("{}.golden", format));
+        fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+    }
+
+    #[test]
+    fn test_matches_golden_output() {
+        for format in [
+            Format::Bash,
+            Format::Json,
+            Format::Fish,
+            Format::PowerShell,
+            Format::Nushell,
+            Format::Tcsh,
+            Format::Dotenv,
+            Format::Systemd,
+            Format::K8sSecret,
+        ] {
+            let rendered = render(format, &adversarial(), &options()).unwrap();
+            assert_eq!(rendered, golden(format), "{}", format);
+        }
+
+        // Docker env-files can't hold line breaks at all
+        let mut secrets = adversarial();
+        assert!(render(Format::Docker, &secrets, &options()).is_err());
+        secrets.retain(|(key, _)| key != "NEWLINES");
+        assert_eq!(render(Format::Docker, &secrets, &options()).unwrap(), golden(Format::Docker));
+    }
+
+    #[test]
+    fn test_nushell_escapes_inside_double_quotes() {
+        let rendered = render(Format::Nushell, &adversarial(), &options()).unwrap();
+        assert!(rendered.contains("  NEWLINES: \"line1\\nline2\\r\\nline3\"\n"));
+        assert!(rendered.contains("  QUOTES: \"it's \\\"quoted\\\" ‘smart’\"\n"));
+    }
+
+    #[test]
+    fn test_shell_names_map_to_formats() {
+        assert_eq!(Format::for_shell("-zsh"), Some(Format::Bash));
+        assert_eq!(Format::for_shell("/opt/homebrew/bin/fish"), Some(Format::Fish));
+        assert_eq!(Format::for_shell("pwsh.exe"), Some(Format::PowerShell));
+        assert_eq!(Format::for_shell("nu"), Some(Format::Nushell));
+        assert_eq!(Format::for_shell("/bin/tcsh"), Some(Format::Tcsh));
+        assert_eq!(Format::for_shell("cargo"), None);
+        assert_eq!("export".parse::<Format>().unwrap(), Format::Bash);
+        assert!("yaml".parse::<Format>().is_err());
+    }
+}
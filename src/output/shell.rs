@@ -0,0 +1,92 @@
+/// `export KEY='value'` for sh, bash, zsh and ksh
+///
+/// Nothing is special inside single quotes except the quote itself, which
+/// is closed, escaped and reopened.
+pub fn posix(key: &str, value: &str) -> String {
+    format!("export {}={}\n", key, posix_quote(value))
+}
+
+/// `value` as one single-quoted POSIX shell word
+pub fn posix_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// `unset KEY` for sh, bash, zsh and ksh
+pub fn posix_unset(key: &str) -> String {
+    format!("unset {}\n", key)
+}
+
+/// `set -gx KEY 'value'` for fish
+///
+/// fish single quotes only interpret `\'` and `\\`.
+pub fn fish(key: &str, value: &str) -> String {
+    format!("set -gx {} {}\n", key, fish_quote(value))
+}
+
+/// `value` as one single-quoted fish word
+pub fn fish_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// `set -e KEY` for fish
+pub fn fish_unset(key: &str) -> String {
+    format!("set -e {}\n", key)
+}
+
+/// `$env:KEY = 'value'` for PowerShell
+///
+/// PowerShell treats the typographic quotes `‘ ’ ‚ ‛` as single quotes too,
+/// so each of them is doubled along with `'`.
+pub fn powershell(key: &str, value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}') {
+            escaped.push(c);
+        }
+        escaped.push(c);
+    }
+    format!("$env:{} = '{}'\n", key, escaped)
+}
+
+/// A nushell record literal, for `from nuon | load-env`
+///
+/// nushell can't `source` generated code, so the record is the whole output.
+pub fn nushell(secrets: &[(String, String)]) -> String {
+    let mut out = String::from("{\n");
+    for (key, value) in secrets {
+        out.push_str(&format!("  {}: \"{}\"\n", key, nushell_escape(value)));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn nushell_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `setenv KEY 'value' ;` for tcsh and csh
+///
+/// History expansion still applies inside single quotes, so `!` is
+/// backslashed, and a newline has to be written as backslash-newline. The
+/// trailing `;` lets ``eval "`...`"`` run the lines as one command list;
+/// values containing newlines only survive `source`, because backquote
+/// substitution splits its output on newlines.
+pub fn tcsh(key: &str, value: &str) -> String {
+    let escaped = value
+        .replace('\'', "'\\''")
+        .replace('!', "\\!")
+        .replace('\n', "\\\n");
+    format!("setenv {} '{}' ;\n", key, escaped)
+}
@@ -0,0 +1,157 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::{Renames, Selector};
+use crate::error::{Error, Result};
+
+/// File name of the per-project manifest
+pub const MANIFEST_NAME: &str = ".keychain.toml";
+
+/// `.keychain.toml`: the secrets a project needs, committed with it
+///
+/// ```toml
+/// service = "claude-dev"            # default: --service-name
+/// keys = ["DB_*", "tag:payments", "!DEBUG_TOKEN"]
+/// strict = true                     # fail if a key is missing
+/// strip_prefix = "PROD_"
+/// prefix = "APP_"
+///
+/// [map]
+/// ANTHROPIC_AUTH_TOKEN = "ANTHROPIC_API_KEY"
+/// GITHUB_TOKEN = ["GITHUB_TOKEN", "GH_TOKEN"]
+/// ```
+///
+/// `keys` takes the same selectors as `--keys` and must not be empty, so a
+/// manifest always says what it asks for.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub service: Option<String>,
+    pub keys: Vec<String>,
+    #[serde(default)]
+    pub strict: bool,
+    #[serde(default)]
+    pub map: BTreeMap<String, Names>,
+    pub strip_prefix: Option<String>,
+    pub prefix: Option<String>,
+}
+
+/// One new name or several for a mapped key
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Names {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Manifest {
+    pub fn parse(content: &str) -> Result<Self> {
+        let manifest: Manifest = toml::from_str(content).map_err(|e| {
+            Error::ValidationError(format!("Invalid {}: {}", MANIFEST_NAME, e))
+        })?;
+        if manifest.keys.is_empty() {
+            return Err(Error::ValidationError(format!(
+                "{} must list at least one selector in `keys`",
+                MANIFEST_NAME
+            )));
+        }
+        Selector::parse(&manifest.keys_spec())?;
+        manifest.renames()?;
+        Ok(manifest)
+    }
+
+    /// `keys` as a `--keys` string
+    pub fn keys_spec(&self) -> String {
+        self.keys.join(",")
+    }
+
+    pub fn renames(&self) -> Result<Renames> {
+        let mut renames = Renames {
+            strip_prefix: self.strip_prefix.clone(),
+            prefix: self.prefix.clone(),
+            ..Renames::default()
+        };
+        for (old, names) in &self.map {
+            let names = match names {
+                Names::One(name) => std::slice::from_ref(name),
+                Names::Many(names) => names.as_slice(),
+            };
+            for new in names {
+                renames.add(&format!("{}={}", old, new))?;
+            }
+        }
+        Ok(renames)
+    }
+}
+
+/// The nearest manifest in `dir` or one of its parents
+pub fn find(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(MANIFEST_NAME))
+        .find(|path| path.is_file())
+}
+
+/// `path` if it is a file, otherwise the nearest manifest from `path` (or
+/// the current directory) upwards
+pub fn locate(path: Option<&Path>) -> Result<PathBuf> {
+    let start = match path {
+        Some(path) if path.is_file() => return Ok(path.to_path_buf()),
+        Some(path) => path.to_path_buf(),
+        None => std::env::current_dir()?,
+    };
+    find(&start).ok_or_else(|| {
+        Error::ValidationError(format!(
+            "No {} in {} or its parents",
+            MANIFEST_NAME,
+            start.display()
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parses_keys_and_renames() {
+        let manifest = Manifest::parse(
+            r#"
+service = "payments"
+keys = ["DB_*", "/^(A|B),C$/", "!DB_DEBUG"]
+prefix = "APP_"
+
+[map]
+ANTHROPIC_AUTH_TOKEN = "ANTHROPIC_API_KEY"
+GITHUB_TOKEN = ["GITHUB_TOKEN", "GH_TOKEN"]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.service.as_deref(), Some("payments"));
+        assert_eq!(manifest.keys_spec(), "DB_*,/^(A|B),C$/,!DB_DEBUG");
+        let renames = manifest.renames().unwrap();
+        assert_eq!(renames.map.len(), 3);
+        assert_eq!(renames.prefix.as_deref(), Some("APP_"));
+    }
+
+    #[test]
+    fn test_rejects_manifests_that_ask_for_nothing_or_too_much() {
+        assert!(Manifest::parse("keys = []").is_err());
+        assert!(Manifest::parse("service = \"x\"").is_err());
+        assert!(Manifest::parse("keys = [\"A\"]\nbackend = \"file\"").is_err());
+        assert!(Manifest::parse("keys = [\"/[/\"]").is_err());
+    }
+
+    #[test]
+    fn test_finds_the_nearest_manifest() {
+        let dir = TempDir::new().unwrap();
+        let nested = dir.path().join("src/deep");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find(&nested), None);
+
+        std::fs::write(dir.path().join(MANIFEST_NAME), "keys = [\"A\"]").unwrap();
+        assert_eq!(find(&nested), Some(dir.path().join(MANIFEST_NAME)));
+    }
+}
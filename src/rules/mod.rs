@@ -39,7 +39,9 @@ impl Rule {
         match &self.rule_type {
             RuleType::Substring { pattern } => {
                 let pattern_lower = pattern.to_lowercase();
-                cmd_lower.contains(&pattern_lower)
+                cmd_lower
+                    .match_indices(&pattern_lower)
+                    .any(|(i, m)| !is_template_suffix(&cmd_lower[i + m.len()..]))
             }
             RuleType::ContainsAll { patterns } => {
                 patterns.iter().all(|p| {
@@ -57,6 +59,24 @@ impl Rule {
     }
 }
 
+/// 模板文件后缀（如 .env.example），不包含真实密钥
+const TEMPLATE_SUFFIXES: [&str; 3] = [".example", ".sample", ".template"];
+
+/// 检查匹配位置之后的文件名剩余部分是否恰好是模板后缀
+///
+/// `.env.example` 放行，`.env.example.real`、`.env.template_prod` 不放行
+fn is_template_suffix(rest: &str) -> bool {
+    TEMPLATE_SUFFIXES.iter().any(|suffix| {
+        rest.strip_prefix(suffix)
+            .is_some_and(|after| after.chars().next().is_none_or(ends_file_name))
+    })
+}
+
+/// 文件名在 shell 命令中的结束字符
+fn ends_file_name(c: char) -> bool {
+    c.is_whitespace() || "'\";|&<>)`".contains(c)
+}
+
 /// 规则引擎：管理和执行所有规则
 pub struct RuleEngine {
     rules: Vec<Rule>,
@@ -107,6 +127,7 @@ impl RuleEngine {
     }
 
     /// 获取所有活跃规则的数量
+    #[allow(dead_code)]
     pub fn active_rules_count(&self) -> usize {
         self.rules.iter().filter(|r| r.enabled).count()
     }
@@ -449,6 +470,26 @@ mod tests {
         assert!(!rule.check("cat .env.example"));
     }
 
+    #[test]
+    fn test_template_suffix_must_end_file_name() {
+        let rule = Rule {
+            id: "test".to_string(),
+            rule_type: RuleType::Substring {
+                pattern: ".env".to_string(),
+            },
+            description: "Test".to_string(),
+            enabled: true,
+        };
+
+        assert!(!rule.check("cp .env.template config/.env.sample"));
+        assert!(!rule.check("cat '.env.example'; ls"));
+        assert!(!rule.check("diff .env.sample|head"));
+        assert!(rule.check("less .env.sample.real"));
+        assert!(rule.check("vim .env.template_prod"));
+        assert!(rule.check("cp .env.example .env"));
+        assert!(rule.check("cat .env.example .env.local"));
+    }
+
     #[test]
     fn test_contains_all_rule() {
         let rule = Rule {
@@ -0,0 +1,1092 @@
+//! End-to-end tests that drive the real binary against the mock backend.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use tempfile::TempDir;
+
+/// Isolated HOME plus a mock store file for one test
+struct Sandbox {
+    home: TempDir,
+}
+
+impl Sandbox {
+    fn new() -> Self {
+        Sandbox {
+            home: TempDir::new().unwrap(),
+        }
+    }
+
+    /// Mock store seeded from a fixture (copied so tests never mutate it)
+    fn seeded(fixture: &str) -> Self {
+        let sandbox = Sandbox::new();
+        fs::copy(fixture_path(fixture), sandbox.store_path()).unwrap();
+        sandbox
+    }
+
+    fn store_path(&self) -> PathBuf {
+        self.home.path().join("store.json")
+    }
+
+    fn backend(&self) -> String {
+        format!("mock:{}", self.store_path().display())
+    }
+
+    fn index(&self) -> serde_json::Value {
+        let path = self.home.path().join(".keychain/claude-dev.index.json");
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    /// The binary with HOME and the mock backend set up, ready for `args`
+    fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_keychain-cli"));
+        command
+            .env("HOME", self.home.path())
+            .args(["--backend", &self.backend()])
+            .args(args);
+        command
+    }
+
+    fn run(&self, args: &[&str]) -> Output {
+        self.command(args).output().unwrap()
+    }
+
+    fn run_with_stdin(&self, args: &[&str], input: &str) -> Output {
+        let mut child = self
+            .command(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        // A command that rejects its arguments may exit before reading stdin
+        if let Err(e) = child.stdin.take().unwrap().write_all(input.as_bytes()) {
+            assert_eq!(e.kind(), std::io::ErrorKind::BrokenPipe, "{}", e);
+        }
+        child.wait_with_output().unwrap()
+    }
+
+    fn stdout(&self, args: &[&str]) -> String {
+        let output = self.run(args);
+        assert!(
+            output.status.success(),
+            "{:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap()
+    }
+}
+
+fn fixture_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+fn setup_sample(sandbox: &Sandbox) {
+    let env_file = fixture_path("sample.env");
+    sandbox.stdout(&["setup", "--env-file", env_file.to_str().unwrap(), "--force"]);
+}
+
+#[test]
+fn setup_stores_only_sensitive_keys() {
+    let sandbox = Sandbox::new();
+    setup_sample(&sandbox);
+
+    let stored: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(sandbox.store_path()).unwrap()).unwrap();
+    assert_eq!(
+        stored,
+        serde_json::json!({
+            "ANTHROPIC_AUTH_TOKEN": "sk-ant-test-0001",
+            "DB_PASSWORD": "it's-a-secret",
+        })
+    );
+}
+
+#[test]
+fn load_bash_format() {
+    let sandbox = Sandbox::new();
+    setup_sample(&sandbox);
+
+    assert_eq!(
+        sandbox.stdout(&["load", "--format", "bash"]),
+        "export ANTHROPIC_AUTH_TOKEN='sk-ant-test-0001'\n\
+         export DB_PASSWORD='it'\\''s-a-secret'\n"
+    );
+}
+
+#[test]
+fn load_export_format() {
+    let sandbox = Sandbox::new();
+    setup_sample(&sandbox);
+
+    assert_eq!(
+        sandbox.stdout(&["load", "--format", "export"]),
+        "export ANTHROPIC_AUTH_TOKEN='sk-ant-test-0001'\n\
+         export DB_PASSWORD='it'\\''s-a-secret'\n"
+    );
+}
+
+#[test]
+fn load_json_format() {
+    let sandbox = Sandbox::new();
+    setup_sample(&sandbox);
+
+    assert_eq!(
+        sandbox.stdout(&["load", "--format", "json"]),
+        "{\n  \"ANTHROPIC_AUTH_TOKEN\": \"sk-ant-test-0001\",\n  \"DB_PASSWORD\": \"it's-a-secret\"\n}\n"
+    );
+}
+
+#[test]
+fn load_filters_by_keys() {
+    let sandbox = Sandbox::new();
+    setup_sample(&sandbox);
+
+    assert_eq!(
+        sandbox.stdout(&["load", "--keys", "DB_PASSWORD"]),
+        "export DB_PASSWORD='it'\\''s-a-secret'\n"
+    );
+}
+
+#[test]
+fn load_rejects_unknown_format() {
+    let sandbox = Sandbox::seeded("seed.json");
+    let output = sandbox.run(&["load", "--format", "yaml"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn load_fails_when_store_is_empty() {
+    let sandbox = Sandbox::new();
+    let output = sandbox.run(&["load"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn load_from_seeded_fixture() {
+    let sandbox = Sandbox::seeded("seed.json");
+    assert_eq!(
+        sandbox.stdout(&["load", "--format", "bash"]),
+        "export GITHUB_TOKEN='ghp_seeded'\n"
+    );
+}
+
+#[test]
+fn check_reports_stored_secrets() {
+    let sandbox = Sandbox::new();
+    setup_sample(&sandbox);
+
+    let stdout = sandbox.stdout(&["check"]);
+    assert!(stdout.contains("Stored Secrets: 2"));
+    assert!(stdout.contains("[✓] Keychain is accessible (found 2 secrets)"));
+}
+
+#[test]
+fn check_warns_when_empty() {
+    let sandbox = Sandbox::new();
+    let stdout = sandbox.stdout(&["check"]);
+    assert!(stdout.contains("WARNING: No secrets found in Keychain!"));
+}
+
+#[test]
+fn setup_records_index_metadata() {
+    let sandbox = Sandbox::new();
+    let env_file = fixture_path("sample.env");
+    sandbox.stdout(&[
+        "setup",
+        "--env-file",
+        env_file.to_str().unwrap(),
+        "--force",
+        "--tags",
+        "ai,prod",
+        "--expires",
+        "2099-01-31",
+    ]);
+
+    let index = sandbox.index();
+    assert_eq!(index["version"], 1);
+    let entry = &index["keys"]["ANTHROPIC_AUTH_TOKEN"];
+    assert_eq!(entry["source"], env_file.canonicalize().unwrap().to_str().unwrap());
+    assert_eq!(entry["tags"], serde_json::json!(["ai", "prod"]));
+    assert_eq!(entry["expires_at"], "2099-01-31T00:00:00Z");
+    assert!(entry["created_at"].is_string());
+
+    let stdout = sandbox.stdout(&["list"]);
+    assert!(stdout.contains("ANTHROPIC_AUTH_TOKEN"));
+    assert!(stdout.contains("tags: ai, prod"));
+    assert!(stdout.contains("expires: 2099-01-31"));
+    assert!(!stdout.contains("sk-ant-test-0001"));
+}
+
+#[test]
+fn legacy_keys_file_is_migrated() {
+    let sandbox = Sandbox::seeded("seed.json");
+    let dir = sandbox.home.path().join(".keychain");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("claude-dev.keys"), "GITHUB_TOKEN\nOLD_KEY").unwrap();
+
+    let stdout = sandbox.stdout(&["list"]);
+    assert!(stdout.contains("GITHUB_TOKEN"));
+    assert!(stdout.contains("OLD_KEY"));
+
+    assert!(!dir.join("claude-dev.keys").exists());
+    assert!(dir.join("claude-dev.keys.migrated").exists());
+    let index = sandbox.index();
+    assert!(index["keys"]["OLD_KEY"]["created_at"].is_string());
+}
+
+#[test]
+fn parallel_setups_keep_every_index_entry() {
+    let sandbox = Sandbox::new();
+    let workers = 16;
+
+    let children: Vec<_> = (0..workers)
+        .map(|i| {
+            let env_file = sandbox.home.path().join(format!("{}.env", i));
+            fs::write(&env_file, format!("WORKER_{}_TOKEN=value-{}\n", i, i)).unwrap();
+            Command::new(env!("CARGO_BIN_EXE_keychain-cli"))
+                .env("HOME", sandbox.home.path())
+                .args(["--backend", "mock", "setup", "--force", "--env-file"])
+                .arg(&env_file)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+
+    for mut child in children {
+        assert!(child.wait().unwrap().success());
+    }
+
+    let index = sandbox.index();
+    let keys = index["keys"].as_object().unwrap();
+    assert_eq!(keys.len(), workers);
+    for i in 0..workers {
+        assert!(keys.contains_key(&format!("WORKER_{}_TOKEN", i)));
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn state_files_are_private() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let sandbox = Sandbox::new();
+    setup_sample(&sandbox);
+
+    let dir = sandbox.home.path().join(".keychain");
+    let mode = |p: &Path| fs::metadata(p).unwrap().permissions().mode() & 0o777;
+    assert_eq!(mode(&dir), 0o700);
+    assert_eq!(mode(&dir.join("claude-dev.index.json")), 0o600);
+    assert_eq!(mode(&dir.join("claude-dev.index.json.lock")), 0o600);
+}
+
+#[test]
+fn delete_removes_secret_and_index_entry() {
+    let sandbox = Sandbox::new();
+    setup_sample(&sandbox);
+
+    let stdout = sandbox.stdout(&["rm", "DB_PASSWORD", "--force"]);
+    assert!(stdout.contains("[✓] DB_PASSWORD deleted"));
+
+    assert_eq!(
+        sandbox.stdout(&["load"]),
+        "export ANTHROPIC_AUTH_TOKEN='sk-ant-test-0001'\n"
+    );
+    assert!(sandbox.index()["keys"].get("DB_PASSWORD").is_none());
+}
+
+#[test]
+fn delete_reports_each_key() {
+    let sandbox = Sandbox::new();
+    setup_sample(&sandbox);
+
+    let output = sandbox.run(&["delete", "DB_PASSWORD,NOPE", "--force"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout.contains("[✓] DB_PASSWORD deleted"));
+    assert!(stdout.contains("[✗] NOPE not found"));
+}
+
+#[test]
+fn delete_all_clears_store_and_stale_entries() {
+    let sandbox = Sandbox::new();
+    setup_sample(&sandbox);
+    fs::write(sandbox.store_path(), "{}").unwrap();
+
+    let stdout = sandbox.stdout(&["delete", "--all", "--force"]);
+    assert!(stdout.contains("removed stale index entry"));
+    assert!(sandbox.index()["keys"].as_object().unwrap().is_empty());
+}
+
+#[test]
+fn list_formats_show_presence_without_values() {
+    let sandbox = Sandbox::new();
+    setup_sample(&sandbox);
+    // Drop one value behind the index's back
+    fs::write(
+        sandbox.store_path(),
+        r#"{"ANTHROPIC_AUTH_TOKEN": "sk-ant-test-0001"}"#,
+    )
+    .unwrap();
+
+    let table = sandbox.stdout(&["list", "--format", "table"]);
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("KEY"));
+    assert!(lines[1].starts_with("ANTHROPIC_AUTH_TOKEN  yes"));
+    assert!(lines[2].starts_with("DB_PASSWORD           MISSING"));
+
+    let json: serde_json::Value =
+        serde_json::from_str(&sandbox.stdout(&["list", "--json"])).unwrap();
+    assert_eq!(json[0]["key"], "ANTHROPIC_AUTH_TOKEN");
+    assert_eq!(json[0]["stored"], true);
+    assert_eq!(json[1]["key"], "DB_PASSWORD");
+    assert_eq!(json[1]["stored"], false);
+
+    for output in [table, json.to_string(), sandbox.stdout(&["list"])] {
+        assert!(!output.contains("sk-ant-test-0001"));
+        assert!(!output.contains("it's-a-secret"));
+    }
+}
+
+#[test]
+fn reconcile_reports_and_fixes_ghosts_and_orphans() {
+    let sandbox = Sandbox::new();
+    setup_sample(&sandbox);
+    // DB_PASSWORD vanished from the store; GITHUB_TOKEN was added out of band
+    fs::write(
+        sandbox.store_path(),
+        r#"{"ANTHROPIC_AUTH_TOKEN": "sk-ant-test-0001", "GITHUB_TOKEN": "ghp_seeded"}"#,
+    )
+    .unwrap();
+
+    let output = sandbox.run(&["reconcile"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout.contains("Ghosts (in index, missing from store): 1\n  [!] DB_PASSWORD"));
+    assert!(stdout.contains("Orphans (in store, missing from index): 1\n  [!] GITHUB_TOKEN"));
+
+    let stdout = sandbox.stdout(&["reconcile", "--fix"]);
+    assert!(stdout.contains("[✓] DB_PASSWORD pruned from index"));
+    assert!(stdout.contains("[✓] GITHUB_TOKEN adopted into index"));
+
+    let index = sandbox.index();
+    assert!(index["keys"].get("DB_PASSWORD").is_none());
+    assert_eq!(index["keys"]["GITHUB_TOKEN"]["source"], "adopted by reconcile");
+
+    assert!(sandbox.stdout(&["reconcile"]).contains("[✓] Index and store are consistent"));
+}
+
+#[test]
+fn setup_parses_quoted_and_exported_values() {
+    let sandbox = Sandbox::new();
+    let env_file = sandbox.home.path().join(".env");
+    fs::write(
+        &env_file,
+        "export API_TOKEN=\"tok en\" # inline comment\nPRIVATE_KEY='-----BEGIN-----\nabc\n-----END-----'\n",
+    )
+    .unwrap();
+    sandbox.stdout(&["setup", "--force", "--env-file", env_file.to_str().unwrap()]);
+
+    let json: serde_json::Value =
+        serde_json::from_str(&sandbox.stdout(&["load", "--format", "json"])).unwrap();
+    assert_eq!(json["API_TOKEN"], "tok en");
+    assert_eq!(json["PRIVATE_KEY"], "-----BEGIN-----\nabc\n-----END-----");
+}
+
+#[test]
+fn setup_reports_parse_errors_with_line_numbers() {
+    let sandbox = Sandbox::new();
+    let env_file = sandbox.home.path().join(".env");
+    fs::write(&env_file, "API_TOKEN=ok\nDB_PASSWORD=\"unterminated\n").unwrap();
+
+    let output = sandbox.run(&["setup", "--force", "--env-file", env_file.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("line 2: unterminated"));
+}
+
+#[test]
+fn setup_explains_classification_and_honours_config() {
+    let sandbox = Sandbox::new();
+    let dir = sandbox.home.path().join(".keychain");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("classify.json"), r#"{"include": ["*_DSN"], "exclude": ["DB_*"]}"#).unwrap();
+
+    let env_file = sandbox.home.path().join(".env");
+    fs::write(
+        &env_file,
+        "DATABASE_URL=postgres://app:pw@db/app\nPUBLIC_KEY_URL=https://example.com/key.pem\n\
+         SENTRY_DSN=https://example.com/1\nDB_PASSWORD=hunter2\n",
+    )
+    .unwrap();
+
+    let stdout = sandbox.stdout(&["setup", "--force", "--env-file", env_file.to_str().unwrap()]);
+    assert!(stdout.contains("  - DATABASE_URL (value is a URL with embedded credentials)"));
+    assert!(stdout.contains("  - SENTRY_DSN (included by pattern '*_DSN')"));
+    assert!(stdout.contains("  - PUBLIC_KEY_URL (name marks it as public)"));
+    assert!(stdout.contains("  - DB_PASSWORD (excluded by pattern 'DB_*')"));
+
+    let stored: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(sandbox.store_path()).unwrap()).unwrap();
+    let keys: Vec<&String> = stored.as_object().unwrap().keys().collect();
+    assert_eq!(keys, ["DATABASE_URL", "SENTRY_DSN"]);
+}
+
+#[test]
+fn setup_all_stores_non_sensitive_values() {
+    let sandbox = Sandbox::new();
+    let env_file = fixture_path("sample.env");
+    let stdout = sandbox.stdout(&["setup", "--all", "--force", "--env-file", env_file.to_str().unwrap()]);
+    assert!(stdout.contains("Including 1 non-sensitive variables:"));
+
+    assert_eq!(
+        sandbox.stdout(&["load", "--keys", "APP_PORT"]),
+        "export APP_PORT='8080'\n"
+    );
+}
+
+#[test]
+fn setup_dry_run_prints_plan_without_writing() {
+    let sandbox = Sandbox::seeded("seed.json");
+    fs::write(
+        sandbox.store_path(),
+        r#"{"ANTHROPIC_AUTH_TOKEN": "sk-ant-test-0001", "DB_PASSWORD": "old", "GITHUB_TOKEN": "ghp_seeded"}"#,
+    )
+    .unwrap();
+    let before = fs::read_to_string(sandbox.store_path()).unwrap();
+
+    let env_file = sandbox.home.path().join(".env");
+    fs::write(
+        &env_file,
+        "ANTHROPIC_AUTH_TOKEN=sk-ant-test-0001\nDB_PASSWORD=new-secret\nSTRIPE_SECRET=sk_live_1\n",
+    )
+    .unwrap();
+    let env_arg = env_file.to_str().unwrap();
+
+    let stdout = sandbox.stdout(&["setup", "--dry-run", "--env-file", env_arg]);
+    assert!(stdout.contains(
+        "  = ANTHROPIC_AUTH_TOKEN (unchanged, skipped)\n  \
+         ~ DB_PASSWORD (changed)\n  \
+         + STRIPE_SECRET (new)\n  \
+         ? GITHUB_TOKEN (only in store, left as is)\n"
+    ));
+    assert!(!stdout.contains("new-secret"));
+    assert!(!stdout.contains("old"));
+    assert!(stdout.contains("Dry run: nothing was written."));
+    assert_eq!(fs::read_to_string(sandbox.store_path()).unwrap(), before);
+
+    let stdout = sandbox.stdout(&["setup", "--force", "--env-file", env_arg]);
+    assert!(stdout.contains("Stored: 2 secrets"));
+    assert!(!stdout.contains("[✓] ANTHROPIC_AUTH_TOKEN stored"));
+}
+
+#[test]
+fn import_flattens_structured_config() {
+    let sandbox = Sandbox::new();
+    let config = sandbox.home.path().join("config.toml");
+    fs::write(
+        &config,
+        "[db]\npassword = \"hunter2\"\nport = 5432\n\n[api]\n\"stripe.secret\" = \"sk_live_1\"\n",
+    )
+    .unwrap();
+
+    let stdout = sandbox.stdout(&["import", "--from", "toml", "--force", config.to_str().unwrap()]);
+    assert!(stdout.contains("Skipping 1 non-sensitive variables:\n  - DB_PORT"));
+    assert_eq!(
+        sandbox.stdout(&["load"]),
+        "export API_STRIPE_SECRET='sk_live_1'\nexport DB_PASSWORD='hunter2'\n"
+    );
+    assert_eq!(
+        sandbox.index()["keys"]["DB_PASSWORD"]["source"],
+        config.canonicalize().unwrap().to_str().unwrap()
+    );
+
+    let json = sandbox.home.path().join("config.json");
+    fs::write(&json, r#"{"db": {"password": "rotated"}}"#).unwrap();
+    sandbox.stdout(&[
+        "import", "--from", "json", "--separator", "__", "--key-case", "preserve", "--force",
+        json.to_str().unwrap(),
+    ]);
+    assert_eq!(
+        sandbox.stdout(&["load", "--keys", "db__password"]),
+        "export db__password='rotated'\n"
+    );
+}
+
+#[test]
+fn import_kubernetes_secret_stores_every_entry() {
+    let sandbox = Sandbox::new();
+    let manifest = sandbox.home.path().join("secret.yaml");
+    fs::write(
+        &manifest,
+        "apiVersion: v1\nkind: Secret\nmetadata:\n  name: app\ndata:\n  username: YWRtaW4=\nstringData:\n  db.password: hunter2\n",
+    )
+    .unwrap();
+
+    let stdout = sandbox.stdout(&["import", "--from", "k8s", "--force", manifest.to_str().unwrap()]);
+    assert!(stdout.contains("USERNAME (value of a Kubernetes Secret)"));
+    assert_eq!(
+        sandbox.stdout(&["load"]),
+        "export DB_PASSWORD='hunter2'\nexport USERNAME='admin'\n"
+    );
+}
+
+#[test]
+fn import_compose_env_files() {
+    let sandbox = Sandbox::new();
+    let dir = sandbox.home.path();
+    fs::copy(fixture_path("sample.env"), dir.join("app.env")).unwrap();
+    fs::write(
+        dir.join("docker-compose.yml"),
+        "services:\n  app:\n    image: app\n    env_file: [app.env]\n",
+    )
+    .unwrap();
+    let compose = dir.join("docker-compose.yml");
+
+    sandbox.stdout(&["import", "--from", "compose", "--force", compose.to_str().unwrap()]);
+    assert_eq!(
+        sandbox.stdout(&["load", "--keys", "DB_PASSWORD"]),
+        "export DB_PASSWORD='it'\\''s-a-secret'\n"
+    );
+
+    let output = sandbox.run(&["import", "--from", "xml", compose.to_str().unwrap()]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn import_password_manager_exports() {
+    let sandbox = Sandbox::new();
+    let bitwarden = fixture_path("import/bitwarden.json");
+    let stdout = sandbox.stdout(&["import", "--from", "bitwarden", "--force", bitwarden.to_str().unwrap()]);
+    assert!(stdout.contains("STRIPE_PASSWORD (entry in a password manager export)"));
+    assert_eq!(
+        sandbox.stdout(&["load"]),
+        "export DEPLOY_NOTES_OPENAI_API_KEY='sk-bw-openai'\n\
+         export STRIPE_PASSWORD='stripe-login-pw'\n\
+         export STRIPE_SECRET_KEY='sk_live_bw'\n"
+    );
+
+    let pass = fixture_path("import/pass");
+    sandbox.stdout(&[
+        "import", "--from", "pass", "--key-template", "{folder}_{title}", "--fields", "password",
+        "--force", pass.to_str().unwrap(),
+    ]);
+    assert_eq!(
+        sandbox.stdout(&["load", "--keys", "GITHUB,WORK_PAYMENTS_POSTGRES"]),
+        "export GITHUB='ghp_pass_token'\nexport WORK_PAYMENTS_POSTGRES='pass-db-pw'\n"
+    );
+}
+
+#[test]
+fn setup_stdin_and_set_keep_values_off_disk() {
+    let sandbox = Sandbox::new();
+
+    let output = sandbox.run_with_stdin(
+        &["setup", "--stdin", "--force"],
+        "DB_PASSWORD='from stdin'\nAPP_PORT=8080\n",
+    );
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(sandbox.index()["keys"]["DB_PASSWORD"]["source"], "stdin");
+
+    let output = sandbox.run_with_stdin(&["set", "STRIPE_SECRET", "--tags", "billing"], "sk_live_1\n");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("STRIPE_SECRET is sensitive"));
+    assert_eq!(sandbox.index()["keys"]["STRIPE_SECRET"]["tags"], serde_json::json!(["billing"]));
+
+    assert_eq!(
+        sandbox.stdout(&["load"]),
+        "export DB_PASSWORD='from stdin'\nexport STRIPE_SECRET='sk_live_1'\n"
+    );
+
+    // Besides the mock store itself, nothing on disk holds a value
+    let mut dirs = vec![sandbox.home.path().to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path != sandbox.store_path() {
+                let content = fs::read_to_string(&path).unwrap_or_default();
+                assert!(!content.contains("sk_live_1") && !content.contains("from stdin"), "{:?}", path);
+            }
+        }
+    }
+
+    let output = sandbox.run_with_stdin(&["set", "1BAD"], "x\n");
+    assert!(!output.status.success());
+    let output = sandbox.run_with_stdin(&["set", "EMPTY"], "\n");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn setup_scrub_replaces_stored_values_with_references() {
+    let sandbox = Sandbox::new();
+    let env_file = sandbox.home.path().join(".env");
+    fs::write(
+        &env_file,
+        "# app config\nexport ANTHROPIC_AUTH_TOKEN=sk-ant-test-0001 # main key\nAPP_PORT=8080\nDB_PASSWORD=\"multi\nline\"\n",
+    )
+    .unwrap();
+    let env_arg = env_file.to_str().unwrap();
+    let backup = sandbox.home.path().join("env.backup");
+
+    let output = sandbox
+        .command(&["setup", "--force", "--scrub", "--scrub-backup", backup.to_str().unwrap(), "--env-file", env_arg])
+        .env("KEYCHAIN_BACKUP_PASSPHRASE", "backup-pass")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    assert_eq!(
+        fs::read_to_string(&env_file).unwrap(),
+        "# app config\n\
+         export ANTHROPIC_AUTH_TOKEN=keychain://claude-dev/ANTHROPIC_AUTH_TOKEN # main key\n\
+         APP_PORT=8080\n\
+         DB_PASSWORD=keychain://claude-dev/DB_PASSWORD\n"
+    );
+    assert_eq!(
+        sandbox.stdout(&["load", "--keys", "DB_PASSWORD"]),
+        "export DB_PASSWORD='multi\nline'\n"
+    );
+    assert!(!fs::read_to_string(&backup).unwrap().contains("sk-ant-test-0001"));
+
+    let restored = sandbox
+        .command(&["restore", backup.to_str().unwrap()])
+        .env("KEYCHAIN_BACKUP_PASSPHRASE", "backup-pass")
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&restored.stdout).contains("ANTHROPIC_AUTH_TOKEN=sk-ant-test-0001 # main key"));
+
+    // Scrubbing again finds nothing left to replace
+    let stdout = sandbox.stdout(&["setup", "--force", "--scrub", "--env-file", env_arg]);
+    assert!(stdout.contains("Nothing to scrub"), "{}", stdout);
+}
+
+#[test]
+fn setup_scrub_leaves_file_alone_when_a_store_fails() {
+    let sandbox = Sandbox::new();
+    let env_file = sandbox.home.path().join(".env");
+    fs::copy(fixture_path("sample.env"), &env_file).unwrap();
+    let before = fs::read_to_string(&env_file).unwrap();
+
+    // The mock store can't write its fixture into a missing directory
+    let broken = format!("mock:{}", sandbox.home.path().join("missing/store.json").display());
+    let output = Command::new(env!("CARGO_BIN_EXE_keychain-cli"))
+        .env("HOME", sandbox.home.path())
+        .args(["--backend", &broken, "setup", "--force", "--scrub", "--env-file"])
+        .arg(&env_file)
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("left untouched"));
+    assert_eq!(fs::read_to_string(&env_file).unwrap(), before);
+}
+
+#[test]
+fn inject_resolves_references_or_names_missing_keys() {
+    let sandbox = Sandbox::new();
+    setup_sample(&sandbox);
+    let template = sandbox.home.path().join(".env.template");
+    fs::write(
+        &template,
+        "# committed template\nTOKEN=keychain://claude-dev/ANTHROPIC_AUTH_TOKEN\nDB: \"{{ keychain \"DB_PASSWORD\" }}\"\nPORT=8080 {{ .Other }}\n",
+    )
+    .unwrap();
+
+    assert_eq!(
+        sandbox.stdout(&["inject", template.to_str().unwrap()]),
+        "# committed template\nTOKEN=sk-ant-test-0001\nDB: \"it's-a-secret\"\nPORT=8080 {{ .Other }}\n"
+    );
+
+    let rendered = sandbox.home.path().join("rendered.env");
+    sandbox.stdout(&["resolve", template.to_str().unwrap(), "-o", rendered.to_str().unwrap()]);
+    assert!(fs::read_to_string(&rendered).unwrap().contains("TOKEN=sk-ant-test-0001"));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(fs::metadata(&rendered).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    fs::write(&rendered, "keep me\n").unwrap();
+    let output = sandbox.run(&["inject", template.to_str().unwrap(), "-o", rendered.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("pass --force"));
+    assert_eq!(fs::read_to_string(&rendered).unwrap(), "keep me\n");
+    sandbox.stdout(&[
+        "inject", template.to_str().unwrap(), "-o", rendered.to_str().unwrap(), "--force",
+    ]);
+    assert!(fs::read_to_string(&rendered).unwrap().contains("TOKEN=sk-ant-test-0001"));
+
+    fs::write(&template, "A={{ keychain \"NOPE\" }}\nB=keychain://claude-dev/ALSO_MISSING\n").unwrap();
+    let missing = sandbox.home.path().join("missing.env");
+    let output = sandbox.run(&["inject", template.to_str().unwrap(), "-o", missing.to_str().unwrap()]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("claude-dev/ALSO_MISSING, claude-dev/NOPE"), "{}", stderr);
+    assert!(!missing.exists());
+}
+
+#[test]
+fn run_passes_secrets_exit_code_and_signals_to_child() {
+    let sandbox = Sandbox::new();
+    setup_sample(&sandbox);
+
+    let output = sandbox
+        .command(&[
+            "run", "--keys", "DB_PASSWORD", "--",
+            "sh", "-c", "echo \"$DB_PASSWORD|${ANTHROPIC_AUTH_TOKEN:-unset}|${RUST_LOG:-none}\"; exit 3",
+        ])
+        .env_remove("RUST_LOG")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "it's-a-secret|unset|none\n");
+
+    let output = sandbox.run(&["run", "--", "definitely-not-a-command-xyz"]);
+    assert_eq!(output.status.code(), Some(127));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        let output = sandbox.run(&["run", "--", "sh", "-c", "kill -TERM $$"]);
+        assert_eq!(output.status.signal(), Some(15));
+    }
+}
+
+#[test]
+fn run_mask_hides_values_in_child_output() {
+    let sandbox = Sandbox::new();
+    setup_sample(&sandbox);
+
+    // The token is written in two pieces to straddle a read boundary
+    let output = sandbox.run(&[
+        "run", "--mask", "--",
+        "sh", "-c",
+        "printf 'token=sk-ant-'; printf 'test-0001\\n'; echo \"db=$DB_PASSWORD\" >&2; exit 4",
+    ]);
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "token=***ANTHROPIC_AUTH_TOKEN***\n");
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("db=***DB_PASSWORD***\n"),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    #[cfg(unix)]
+    {
+        use std::io::{BufRead, BufReader};
+        use std::os::unix::process::ExitStatusExt;
+
+        let output = sandbox.run(&["run", "--mask", "--", "sh", "-c", "kill -TERM $$"]);
+        assert_eq!(output.status.signal(), Some(15));
+
+        // SIGTERM sent to the wrapper reaches the child
+        let mut child = sandbox
+            .command(&[
+                "run", "--mask", "--",
+                "sh", "-c", "trap 'echo stopped; exit 7' TERM; echo ready; sleep 10 & wait",
+            ])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut line = String::new();
+        stdout.read_line(&mut line).unwrap();
+        assert_eq!(line, "ready\n");
+
+        Command::new("kill").arg("-TERM").arg(child.id().to_string()).status().unwrap();
+        line.clear();
+        stdout.read_line(&mut line).unwrap();
+        assert_eq!(line, "stopped\n");
+        assert_eq!(child.wait().unwrap().code(), Some(7));
+    }
+}
+
+#[test]
+fn load_output_round_trips_through_the_shell() {
+    let sandbox = Sandbox::new();
+    let values = serde_json::json!({
+        "QUOTES": "it's \"quoted\" ‘smart’",
+        "EXPANSIONS": "$HOME ${USER} $(id) `id` !! \\n \\",
+        "NEWLINES": "line1\nline2\r\n",
+        "UNICODE": "héllo ✓ 🔑\ttab",
+    });
+    fs::write(sandbox.store_path(), values.to_string()).unwrap();
+
+    // Run from bash so `--format auto` sees it as the parent shell
+    let load = format!(
+        "{} --backend {} load --format auto",
+        env!("CARGO_BIN_EXE_keychain-cli"),
+        sandbox.backend()
+    );
+    let script = format!(
+        "eval \"$({})\" && printf '%s\\0' \"$QUOTES\" \"$EXPANSIONS\" \"$NEWLINES\" \"$UNICODE\"",
+        load
+    );
+    let output = Command::new("bash")
+        .args(["-c", &script])
+        .env("HOME", sandbox.home.path())
+        .env("SHELL", "/usr/bin/fish")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let printed = String::from_utf8(output.stdout).unwrap();
+    let printed: Vec<&str> = printed.trim_end_matches('\0').split('\0').collect();
+    assert_eq!(
+        printed,
+        [
+            "it's \"quoted\" ‘smart’",
+            "$HOME ${USER} $(id) `id` !! \\n \\",
+            "line1\nline2\r\n",
+            "héllo ✓ 🔑\ttab",
+        ]
+    );
+
+    // Without a shell as parent, $SHELL decides
+    let output = sandbox
+        .command(&["load", "--format", "auto", "--keys", "QUOTES"])
+        .env("SHELL", "/usr/bin/fish")
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "set -gx QUOTES 'it\\'s \"quoted\" ‘smart’'\n"
+    );
+}
+
+#[test]
+fn load_file_formats_round_trip_into_setup_and_import() {
+    let sandbox = Sandbox::new();
+    let values = serde_json::json!({
+        "QUOTES": "it's \"quoted\" # not a comment",
+        "EXPANSIONS": "$HOME ${USER} `id` \\$x",
+        "NEWLINES": "line1\nline2\r\n",
+    });
+    fs::write(sandbox.store_path(), values.to_string()).unwrap();
+
+    let dotenv = sandbox.stdout(&["load", "--format", "dotenv"]);
+    let copy = Sandbox::new();
+    let output =
+        copy.run_with_stdin(&["setup", "--stdin", "--all", "--force", "--interpolate"], &dotenv);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stored: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(copy.store_path()).unwrap()).unwrap();
+    assert_eq!(stored, values);
+
+    let manifest = sandbox.stdout(&["load", "--format", "k8s-secret", "--namespace", "prod"]);
+    assert!(manifest.contains("  name: claude-dev\n  namespace: prod\n"), "{}", manifest);
+    let path = sandbox.home.path().join("secret.yaml");
+    fs::write(&path, manifest).unwrap();
+    let copy = Sandbox::new();
+    copy.stdout(&[
+        "import", path.to_str().unwrap(), "--from", "k8s", "--key-case", "preserve", "--force",
+    ]);
+    let stored: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(copy.store_path()).unwrap()).unwrap();
+    assert_eq!(stored, values);
+
+    let output = sandbox.run(&["load", "--format", "docker"]);
+    assert!(!output.status.success(), "newlines can't go in an env-file");
+    let output = sandbox.run(&["load", "--format", "dotenv", "--name", "x"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn load_and_run_rename_keys() {
+    let sandbox = Sandbox::new();
+    setup_sample(&sandbox);
+
+    assert_eq!(
+        sandbox.stdout(&[
+            "load", "--map", "ANTHROPIC_AUTH_TOKEN=ANTHROPIC_API_KEY", "--prefix", "APP_",
+        ]),
+        "export ANTHROPIC_API_KEY='sk-ant-test-0001'\n\
+         export APP_DB_PASSWORD='it'\\''s-a-secret'\n"
+    );
+
+    let map_file = sandbox.home.path().join("tool.map");
+    fs::write(
+        &map_file,
+        "# one stored token, two names\n\
+         ANTHROPIC_AUTH_TOKEN=ANTHROPIC_API_KEY\n\
+         ANTHROPIC_AUTH_TOKEN=ANTHROPIC_AUTH_TOKEN\n",
+    )
+    .unwrap();
+    let output = sandbox.run(&[
+        "run", "--keys", "ANTHROPIC_AUTH_TOKEN", "--map-file", map_file.to_str().unwrap(), "--",
+        "sh", "-c", "echo \"$ANTHROPIC_API_KEY $ANTHROPIC_AUTH_TOKEN\"",
+    ]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "sk-ant-test-0001 sk-ant-test-0001\n");
+
+    let output = sandbox.run(&["load", "--map", "DB_PASSWORD=ANTHROPIC_AUTH_TOKEN"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("would both be exposed"));
+
+    // Stored names that aren't variables only matter once renamed for a shell
+    let output = sandbox.run_with_stdin(&["setup", "--stdin", "--all", "--force"], "my-key=v1\n");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let json: serde_json::Value =
+        serde_json::from_str(&sandbox.stdout(&["load", "--format", "json", "--prefix", "APP_"]))
+            .unwrap();
+    assert_eq!(json["APP_my-key"], "v1");
+    assert!(sandbox.stdout(&["load", "--format", "dotenv"]).contains("my-key=v1"));
+    let output = sandbox.run(&["load", "--prefix", "APP_"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("'APP_my-key', which is not a valid"));
+}
+
+#[test]
+fn load_keys_selectors_and_strict() {
+    let sandbox = Sandbox::new();
+    setup_sample(&sandbox);
+    let output =
+        sandbox.run_with_stdin(&["set", "STRIPE_SECRET", "--tags", "billing"], "sk_live_1\n");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let names = |keys: &str| -> Vec<String> {
+        let json: serde_json::Value =
+            serde_json::from_str(&sandbox.stdout(&["load", "--format", "json", "--keys", keys]))
+                .unwrap();
+        json.as_object().unwrap().keys().cloned().collect()
+    };
+    assert_eq!(names("*_TOKEN,tag:billing"), ["ANTHROPIC_AUTH_TOKEN", "STRIPE_SECRET"]);
+    assert_eq!(names("!/^(DB|STRIPE)_/"), ["ANTHROPIC_AUTH_TOKEN"]);
+
+    // Without --strict a typo is only a warning
+    let output = sandbox.run(&["load", "--keys", "DB_PASSWORD,DB_PASWORD"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "export DB_PASSWORD='it'\\''s-a-secret'\n"
+    );
+
+    let output =
+        sandbox.run(&["load", "--strict", "--keys", "DB_PASSWORD,DB_PASWORD,tag:nope,AWS_*"]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("DB_PASWORD, tag:nope, AWS_*"), "{}", stderr);
+
+    let output = sandbox.run(&["run", "--strict", "--keys", "NOPE", "--", "true"]);
+    assert!(!output.status.success());
+
+    let output = sandbox.run(&["run", "--keys", "NOPE,!DB_*", "--", "true"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--keys 'NOPE,!DB_*' matched none"), "{}", stderr);
+}
+
+#[test]
+fn project_manifest_loads_only_once_trusted() {
+    let sandbox = Sandbox::new();
+    setup_sample(&sandbox);
+    let project = sandbox.home.path().join("project");
+    fs::create_dir_all(project.join("src")).unwrap();
+    let manifest = project.join(".keychain.toml");
+    fs::write(
+        &manifest,
+        "keys = [\"DB_PASSWORD\", \"ANTHROPIC_AUTH_TOKEN\"]\n\n\
+         [map]\nANTHROPIC_AUTH_TOKEN = \"ANTHROPIC_API_KEY\"\n",
+    )
+    .unwrap();
+
+    // $1 is the hook for `service`, `kc` the binary on the sandbox store
+    let bash = |service: &str, script: &str| -> Output {
+        let hook = sandbox.stdout(&["--service-name", service, "hook-shell", "bash"]);
+        let script = format!(
+            "eval \"$1\"\nbin=$2 backend=$3\nkc() {{ \"$bin\" --backend \"$backend\" \"$@\"; }}\n{}",
+            script
+        );
+        Command::new("bash")
+            .current_dir(sandbox.home.path())
+            .env("HOME", sandbox.home.path())
+            .env("DB_PASSWORD", "mine")
+            .env_remove("KEYCHAIN_CLI_PROJECT")
+            .env_remove("KEYCHAIN_CLI_KEYS")
+            .env_remove("KEYCHAIN_CLI_SAVED")
+            .env_remove("ANTHROPIC_API_KEY")
+            .args(["-c", &script, "bash", &hook, env!("CARGO_BIN_EXE_keychain-cli")])
+            .arg(sandbox.backend())
+            .output()
+            .unwrap()
+    };
+    // Enter the project, print what is exported, then leave again
+    let visit = || {
+        bash(
+            "claude-dev",
+            "cd project/src && _keychain_cli_hook\n\
+             echo \"in: ${ANTHROPIC_API_KEY-unset} ${DB_PASSWORD-unset}\"\n\
+             cd ../.. && _keychain_cli_hook\n\
+             echo \"out: ${ANTHROPIC_API_KEY-unset} ${DB_PASSWORD-unset} ${KEYCHAIN_CLI_PROJECT-unset}\"\n",
+        )
+    };
+
+    let output = visit();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "in: unset mine\nout: unset mine unset\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("is not trusted"));
+
+    // Trusted, it loads, and leaving puts back the DB_PASSWORD the shell had
+    let allowed = sandbox.run(&["allow", project.to_str().unwrap(), "--yes"]);
+    assert!(allowed.status.success(), "{}", String::from_utf8_lossy(&allowed.stderr));
+    let output = visit();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "in: sk-ant-test-0001 it's-a-secret\nout: unset mine unset\n"
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("loaded ANTHROPIC_API_KEY, DB_PASSWORD"), "{}", stderr);
+
+    // Any edit needs approving again
+    fs::write(&manifest, "keys = [\"NEW_TOKEN\"]\nstrict = true\n").unwrap();
+    let output = visit();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "in: unset mine\nout: unset mine unset\n");
+
+    // A failed load is reported once and retried until it works
+    sandbox.stdout(&["allow", manifest.to_str().unwrap(), "--yes"]);
+    let output = bash(
+        "claude-dev",
+        "cd project && _keychain_cli_hook && _keychain_cli_hook\n\
+         echo \"before: ${NEW_TOKEN-unset}\"\n\
+         echo tok-123 | kc set NEW_TOKEN >/dev/null 2>&1\n\
+         _keychain_cli_hook\n\
+         echo \"after: ${NEW_TOKEN-unset}\"\n",
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "before: unset\nafter: tok-123\n", "{}", stderr);
+    assert_eq!(stderr.matches("Not found in service").count(), 1, "{}", stderr);
+
+    // Approval covers the service shown at the time, not whatever a hook reads
+    let enter = "cd project && _keychain_cli_hook\necho \"in: ${DB_PASSWORD-unset}\"\n";
+    fs::write(&manifest, "keys = [\"DB_PASSWORD\"]\n").unwrap();
+    sandbox.stdout(&["--service-name", "other", "allow", manifest.to_str().unwrap(), "--yes"]);
+    let output = bash("claude-dev", enter);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "in: mine\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("not trusted to read service claude-dev"), "{}", stderr);
+    assert_eq!(String::from_utf8_lossy(&bash("other", enter).stdout), "in: it's-a-secret\n");
+
+    // A manifest's own service wins over the hook's
+    fs::write(&manifest, "service = \"payments\"\nkeys = [\"DB_PASSWORD\"]\n").unwrap();
+    let allowed = sandbox.stdout(&["allow", manifest.to_str().unwrap(), "--yes"]);
+    assert!(allowed.contains("Trusted"), "{}", allowed);
+    let trusted = fs::read_to_string(sandbox.home.path().join(".keychain/trusted.json")).unwrap();
+    assert!(trusted.contains("\"service\": \"payments\""), "{}", trusted);
+    assert_eq!(String::from_utf8_lossy(&bash("other", enter).stdout), "in: it's-a-secret\n");
+
+    let denied = sandbox.stdout(&["deny", manifest.to_str().unwrap()]);
+    assert!(denied.contains("Revoked trust"), "{}", denied);
+    let denied = sandbox.stdout(&["deny", manifest.to_str().unwrap()]);
+    assert!(denied.contains("was not trusted"), "{}", denied);
+
+    let output = sandbox.run(&["hook-shell", "tcsh"]);
+    assert!(!output.status.success());
+}
@@ -0,0 +1,173 @@
+//! Keychain backend tests against a fake `security` binary on PATH.
+//!
+//! They check that secret values never reach the `security` argv, that
+//! values read back exactly as stored, and that Keychain errors (a locked
+//! Keychain) are not mistaken for missing items.
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Command;
+
+use tempfile::TempDir;
+
+const SECRET: &str = "sk-ant-argv-canary-7731";
+
+/// Fake `security` that logs argv/stdin and fails if it sees the canary in argv;
+/// `find-generic-password` answers for PEM_KEY and PADDED and otherwise exits
+/// with `$SHIM_FIND_STATUS` (default 44, item not found)
+const SHIM: &str = r#"#!/bin/sh
+printf '%s\n' "$*" >> "$SHIM_LOG/argv.log"
+case "$*" in
+  *"$SHIM_FORBIDDEN"*)
+    echo "secret value leaked into argv" >&2
+    exit 99
+    ;;
+esac
+if [ "$1" = "-i" ]; then
+  cat >> "$SHIM_LOG/stdin.log"
+fi
+if [ "$1" = "find-generic-password" ]; then
+  case "$*" in
+    *"-s PEM_KEY -w"*) printf '%s\n' "$SHIM_PEM_HEX" ;;
+    *"-s PADDED -w"*) printf '  padded value \n' ;;
+    *) exit "${SHIM_FIND_STATUS:-44}" ;;
+  esac
+fi
+exit 0
+"#;
+
+const PEM: &str = "-----BEGIN KEY-----\nMIIB\n-----END KEY-----\n";
+
+fn hex(s: &str) -> String {
+    s.bytes().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// The binary with `security` on PATH replaced by the shim
+fn keychain_cli(home: &Path) -> Command {
+    let bin_dir = home.join("bin");
+    if !bin_dir.exists() {
+        fs::create_dir(&bin_dir).unwrap();
+        let shim = bin_dir.join("security");
+        fs::write(&shim, SHIM).unwrap();
+        fs::set_permissions(&shim, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    let path = format!("{}:{}", bin_dir.display(), std::env::var("PATH").unwrap());
+    let mut command = Command::new(env!("CARGO_BIN_EXE_keychain-cli"));
+    command
+        .env("HOME", home)
+        .env("PATH", path)
+        .env("SHIM_LOG", home)
+        .env("SHIM_FORBIDDEN", SECRET)
+        .env("SHIM_PEM_HEX", hex(PEM))
+        .args(["--backend", "keychain"]);
+    command
+}
+
+#[test]
+fn store_keeps_values_out_of_argv() {
+    let home = TempDir::new().unwrap();
+    let env_file = home.path().join(".env");
+    fs::write(&env_file, format!("ANTHROPIC_AUTH_TOKEN={}\n", SECRET)).unwrap();
+
+    let output = keychain_cli(home.path())
+        .args(["setup", "--force"])
+        .arg("--env-file")
+        .arg(&env_file)
+        .output()
+        .unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("[✓] ANTHROPIC_AUTH_TOKEN stored"), "{}", stdout);
+
+    let argv = read_log(home.path(), "argv.log");
+    assert!(!argv.contains(SECRET));
+    assert!(!argv.contains(&hex(SECRET)));
+
+    let stdin = read_log(home.path(), "stdin.log");
+    assert!(stdin.contains("add-generic-password"));
+    assert!(stdin.contains(&hex(SECRET)));
+}
+
+#[test]
+fn retrieve_decodes_hex_and_keeps_whitespace() {
+    let home = TempDir::new().unwrap();
+    let env_file = home.path().join(".env");
+    fs::write(&env_file, "PEM_KEY=\"x\"\nPADDED=\"x\"\n").unwrap();
+    let output = keychain_cli(home.path())
+        .args(["setup", "--all", "--force"])
+        .arg("--env-file")
+        .arg(&env_file)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let output = keychain_cli(home.path()).args(["load", "--format", "json"]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["PEM_KEY"], PEM);
+    assert_eq!(json["PADDED"], "  padded value ");
+}
+
+#[test]
+fn reconcile_fix_keeps_index_when_keychain_errors() {
+    let home = TempDir::new().unwrap();
+    let env_file = home.path().join(".env");
+    fs::write(&env_file, format!("ANTHROPIC_AUTH_TOKEN={}\n", SECRET)).unwrap();
+    let output = keychain_cli(home.path())
+        .args(["setup", "--force"])
+        .arg("--env-file")
+        .arg(&env_file)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let index = home.path().join(".keychain/claude-dev.index.json");
+
+    // 36: errSecInteractionNotAllowed, as for a locked Keychain
+    let output = keychain_cli(home.path())
+        .env("SHIM_FIND_STATUS", "36")
+        .args(["reconcile", "--fix"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(fs::read_to_string(&index).unwrap().contains("ANTHROPIC_AUTH_TOKEN"));
+
+    // 44: errSecItemNotFound, a real ghost
+    let output = keychain_cli(home.path()).args(["reconcile", "--fix"]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("ANTHROPIC_AUTH_TOKEN pruned"));
+    assert!(!fs::read_to_string(&index).unwrap().contains("ANTHROPIC_AUTH_TOKEN"));
+}
+
+#[test]
+fn delete_keeps_index_when_keychain_errors() {
+    let home = TempDir::new().unwrap();
+    let env_file = home.path().join(".env");
+    fs::write(&env_file, format!("ANTHROPIC_AUTH_TOKEN={}\n", SECRET)).unwrap();
+    let output = keychain_cli(home.path())
+        .args(["setup", "--force"])
+        .arg("--env-file")
+        .arg(&env_file)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let index = home.path().join(".keychain/claude-dev.index.json");
+
+    // 36: errSecInteractionNotAllowed, as for a locked Keychain
+    let output = keychain_cli(home.path())
+        .env("SHIM_FIND_STATUS", "36")
+        .args(["delete", "ANTHROPIC_AUTH_TOKEN", "--force"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("[✗] ANTHROPIC_AUTH_TOKEN failed"));
+    assert!(fs::read_to_string(&index).unwrap().contains("ANTHROPIC_AUTH_TOKEN"));
+    assert!(!read_log(home.path(), "argv.log").contains("delete-generic-password"));
+}
+
+fn read_log(dir: &Path, name: &str) -> String {
+    fs::read_to_string(dir.join(name)).unwrap_or_default()
+}